@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, collections::HashSet, fmt::Display};
+use std::{collections::HashSet, fmt::Display};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Suit {
@@ -87,14 +87,7 @@ impl TryFrom<&str> for Card {
             return Ok(Card::Joker(None));
         }
         let chars = e.chars().collect::<Vec<char>>();
-        let n: u8 = match chars[0] {
-            'A' => Ok(1),
-            'T' => Ok(10),
-            'J' => Ok(11),
-            'Q' => Ok(12),
-            'K' => Ok(13),
-            n => n.to_string().parse(),
-        }?;
+        let n = number_from_str(&chars[0].to_string())?;
         let s = match chars[1] {
             'h' => Ok(Suit::Heart),
             'd' => Ok(Suit::Diamond),
@@ -106,32 +99,46 @@ impl TryFrom<&str> for Card {
     }
 }
 
+/// A-K
+pub fn number_str(n: u8) -> String {
+    match n {
+        1 => "A".to_string(),
+        10 => "T".to_string(),
+        11 => "J".to_string(),
+        12 => "Q".to_string(),
+        13 => "K".to_string(),
+        _ => n.to_string(),
+    }
+}
+
+/// inverse of `number_str`
+pub fn number_from_str(s: &str) -> Result<u8> {
+    let n = match s {
+        "A" => 1,
+        "T" => 10,
+        "J" => 11,
+        "Q" => 12,
+        "K" => 13,
+        n => n.parse()?,
+    };
+    if !(1..=13).contains(&n) {
+        return Err(anyhow!("invalid number {}", s));
+    }
+    Ok(n)
+}
+
 impl Display for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fn s(n: u8) -> String {
-            match n {
-                1 => "A".to_string(),
-                10 => "T".to_string(),
-                11 => "J".to_string(),
-                12 => "Q".to_string(),
-                13 => "K".to_string(),
-                _ => n.to_string(),
-            }
-        }
         match self {
-            Card::Number(suit, number) => write!(f, "{}{}", s(*number), suit),
+            Card::Number(suit, number) => write!(f, "{}{}", number_str(*number), suit),
             Card::Joker(None) => write!(f, "joker"),
-            Card::Joker(Some((suit, number))) => write!(f, "joker(as {}{})", s(*number), suit),
+            Card::Joker(Some((suit, number))) => {
+                write!(f, "joker(as {}{})", number_str(*number), suit)
+            }
         }
     }
 }
 
-pub fn is_same_number(cards: &[Card]) -> bool {
-    let numbers: HashSet<_> = cards.iter().filter_map(|c| c.number()).collect();
-    // if only jokers, len == 0
-    numbers.len() <= 1
-}
-
 pub fn numbers(cards: &[Card]) -> HashSet<u8> {
     cards
         .iter()
@@ -141,11 +148,6 @@ pub fn numbers(cards: &[Card]) -> HashSet<u8> {
         .collect::<HashSet<_>>()
 }
 
-/// returns cards number, if only jokers, returns 14
-pub fn number(cards: &[Card]) -> u8 {
-    numbers(cards).into_iter().next().unwrap_or(14)
-}
-
 pub fn suits(cards: &[Card]) -> HashSet<Suit> {
     cards
         .iter()
@@ -153,23 +155,3 @@ pub fn suits(cards: &[Card]) -> HashSet<Suit> {
         .filter(|s| s != &Suit::UnSuited)
         .collect::<HashSet<_>>()
 }
-
-/// `other` contains all suits of `self`
-pub fn match_suits(lhs: &[Card], rhs: &[Card]) -> bool {
-    let (lhs, rhs) = (suits(lhs), suits(rhs));
-    rhs.is_superset(&lhs)
-}
-
-pub fn cardinal(n: u8) -> i32 {
-    ((n + 10) % 13).into()
-}
-
-pub fn card_ord(l: &Card, r: &Card) -> Ordering {
-    let (ln, rn) = (l.number(), r.number());
-    match (ln, rn) {
-        (None, None) => Ordering::Less,
-        (Some(_), None) => Ordering::Less,
-        (None, Some(_)) => Ordering::Greater,
-        (Some(i), Some(j)) => cardinal(i).cmp(&cardinal(j)),
-    }
-}
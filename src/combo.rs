@@ -0,0 +1,57 @@
+use crate::card::{numbers, suits, Card, Suit};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ComboKind {
+    /// one card
+    Single,
+    /// two or more cards of the same number
+    Group,
+}
+
+/// cards served at once, parsed and validated once
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Combo {
+    pub kind: ComboKind,
+    /// `None` if only jokers
+    pub rank: Option<u8>,
+    pub size: usize,
+    /// suits of non-joker cards
+    pub suits: HashSet<Suit>,
+    pub jokers: usize,
+    pub server: String,
+    pub cards: Vec<Card>,
+}
+
+impl Combo {
+    pub fn new(server: String, cards: Vec<Card>) -> Result<Self> {
+        if cards.is_empty() {
+            return Err(anyhow!("please select cards"));
+        }
+        let ranks = numbers(&cards);
+        if ranks.len() > 1 {
+            return Err(anyhow!("not same number"));
+        }
+        let kind = if cards.len() == 1 {
+            ComboKind::Single
+        } else {
+            ComboKind::Group
+        };
+        Ok(Self {
+            kind,
+            rank: ranks.into_iter().next(),
+            size: cards.len(),
+            suits: suits(&cards),
+            jokers: cards.iter().filter(|c| matches!(c, Card::Joker(_))).count(),
+            server,
+            cards,
+        })
+    }
+
+    /// jokers fill the suits this combo lacks
+    pub fn matches_suits(&self, suits: &HashSet<Suit>) -> bool {
+        suits.difference(&self.suits).count() <= self.jokers
+    }
+}
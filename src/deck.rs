@@ -1,8 +1,8 @@
-use crate::card::{card_ord, Card, Suit};
+use crate::card::{Card, Suit};
 use anyhow::{anyhow, Result};
 use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, collections::HashSet};
+use std::cmp::Ordering;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Deck(pub Vec<Card>);
@@ -59,15 +59,17 @@ impl Deck {
     }
 }
 
+/// removes one occurrence per item of `removes`, so equal items such as jokers are removed as many times as given
 fn remove_items<T: Eq + Clone>(items: &mut Vec<T>, removes: &[T]) -> Result<()> {
-    if removes.iter().any(|i| !items.contains(i)) {
-        return Err(anyhow!("remove items not in items"));
+    let mut indices = vec![];
+    for remove in removes {
+        let index = items
+            .iter()
+            .enumerate()
+            .position(|(i, item)| item == remove && !indices.contains(&i))
+            .ok_or(anyhow!("remove items not in items"))?;
+        indices.push(index);
     }
-    let indices = removes
-        .iter()
-        .map(|c| items.iter().position(|h| h == c))
-        .collect::<Option<Vec<_>>>()
-        .unwrap_or_default();
     *items = items
         .iter()
         .enumerate()
@@ -77,17 +79,17 @@ fn remove_items<T: Eq + Clone>(items: &mut Vec<T>, removes: &[T]) -> Result<()>
     Ok(())
 }
 
-fn vec_ord<T, F>(l: impl Iterator<Item = T>, r: impl Iterator<Item = T>, ord: F) -> Ordering
-where
-    F: Fn(T, T) -> Ordering,
-{
-    let orderings = l.zip(r).map(|(a, b)| ord(a, b)).collect::<HashSet<_>>();
-    orderings.into_iter().next().unwrap_or(Ordering::Equal)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::cards;
 
-pub fn deck_ord(lhs: &[Card], rhs: &[Card]) -> Ordering {
-    let (mut lhs, mut rhs) = (lhs.to_vec(), rhs.to_vec());
-    lhs.sort_by(card_ord);
-    rhs.sort_by(card_ord);
-    vec_ord(lhs.iter(), rhs.iter(), card_ord)
+    #[test]
+    fn removes_one_copy_per_card() {
+        let mut deck = Deck::new(cards("joker 4s joker joker"));
+        deck.remove(&cards("joker joker")).unwrap();
+        assert_eq!(deck.0, cards("4s joker"));
+        assert!(deck.remove(&cards("joker joker")).is_err());
+        assert_eq!(deck.0, cards("4s joker"));
+    }
 }
@@ -0,0 +1,536 @@
+use crate::{
+    card::Suit,
+    combo::Combo,
+    effect_def::DefinedEffect,
+    events::answer::answer_handler,
+    game::{FieldKey, Game, Prompt, PromptKind},
+    rules::{PassTarget, Rules},
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Debug};
+
+/// steps a serve resolves in, in order
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Phase {
+    /// the river size is set, then changed by effects
+    Size,
+    /// revolution and 11-back
+    Strength,
+    /// step and suit locks
+    Locks,
+    /// effects of later serves are disabled
+    Suppression,
+    /// prompts are opened, the last one opened is answered first
+    Prompts,
+    /// cuts flush the river at once, even with a prompt open.
+    /// players to advance are applied once every prompt is answered
+    Turn,
+}
+
+impl Phase {
+    pub fn order() -> [Phase; 6] {
+        [
+            Phase::Size,
+            Phase::Strength,
+            Phase::Locks,
+            Phase::Suppression,
+            Phase::Prompts,
+            Phase::Turn,
+        ]
+    }
+}
+
+/// what a phase of a serve changed, kept for the last serve
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Resolution {
+    pub phase: Phase,
+    pub server: String,
+    pub description: String,
+}
+
+/// behaviour of a number, every hook defaults to no effect
+pub trait CardEffect {
+    /// applied in `phase` when `combo` served by `player_id` resolves
+    fn on_serve(
+        &self,
+        _phase: Phase,
+        _game: &mut Game,
+        _player_id: &str,
+        _combo: &Combo,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// players to advance after `combo`, 0 means the server moves again
+    fn on_end_turn(&self, _game: &Game, _combo: &Combo) -> i32 {
+        1
+    }
+
+    /// field the river is flushed to while `combo` is on top
+    fn on_flush(&self, _game: &Game, _combo: &Combo) -> FieldKey {
+        FieldKey::Trushes
+    }
+
+    fn is_suppressed(&self, game: &Game, number: u8) -> bool {
+        game.effect_limits.contains(&number)
+    }
+}
+
+/// one effect per number, built from the rules
+pub struct EffectRegistry(HashMap<u8, Box<dyn CardEffect>>);
+
+impl Debug for EffectRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut numbers = self.0.keys().collect::<Vec<_>>();
+        numbers.sort();
+        f.debug_tuple("EffectRegistry").field(&numbers).finish()
+    }
+}
+
+impl EffectRegistry {
+    pub fn new(rules: &Rules) -> Self {
+        let mut effects = Self::builtin(rules);
+        for n in 1..=13 {
+            let defs = rules
+                .effects
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| d.trigger.rank.is_none_or(|rank| rank == n))
+                .map(|(i, d)| (i, d.clone()))
+                .collect::<Vec<_>>();
+            if defs.is_empty() {
+                continue;
+            }
+            let base = effects.remove(&n);
+            let base = base.filter(|_| defs.iter().all(|(_, d)| d.trigger.rank.is_none()));
+            effects.insert(n, Box::new(DefinedEffect { base, defs }));
+        }
+        Self(effects)
+    }
+
+    fn builtin(rules: &Rules) -> HashMap<u8, Box<dyn CardEffect>> {
+        let mut effects: HashMap<u8, Box<dyn CardEffect>> = HashMap::new();
+        effects.insert(3, Box::new(SuppressAll));
+        effects.insert(4, Box::new(RetrieveTrushes));
+        effects.insert(5, Box::new(SkipPlayers));
+        effects.insert(6, Box::new(NoEffect));
+        effects.insert(
+            7,
+            Box::new(PassCards {
+                target: rules.seven_pass.clone(),
+            }),
+        );
+        effects.insert(
+            8,
+            Box::new(EightCut {
+                single_only: rules.eight_cut_single_only,
+            }),
+        );
+        effects.insert(9, Box::new(FlipRiverSize));
+        if rules.ten_discard {
+            effects.insert(10, Box::new(DiscardCards));
+        } else {
+            effects.insert(10, Box::new(SuppressBelowTen));
+        }
+        effects.insert(11, Box::new(ElevenBack));
+        effects.insert(12, Box::new(StepLock));
+        effects.insert(13, Box::new(RetrieveExcluded));
+        effects.insert(1, Box::new(ServeAgain));
+        effects.insert(2, Box::new(Exclude));
+        effects
+    }
+
+    pub fn get(&self, number: u8) -> Option<&dyn CardEffect> {
+        self.0.get(&number).map(|e| e.as_ref())
+    }
+}
+
+/// what `phase` of `combo` changed from `before` to `after`
+pub fn describe(phase: Phase, before: &Game, after: &Game, combo: &Combo) -> Vec<String> {
+    let mut effects = vec![];
+    match phase {
+        Phase::Size => {
+            if after.river_size != Some(combo.size) {
+                let size = after.river_size.unwrap_or_default();
+                effects.push(format!("river size becomes {}", size));
+            }
+        }
+        Phase::Strength => {
+            if after.revoluted != before.revoluted {
+                effects.push("revolution".to_string());
+            }
+            if after.turn_revoluted && !before.turn_revoluted {
+                effects.push("11-back until the river is reset".to_string());
+            }
+        }
+        Phase::Locks => {
+            if after.is_step && !before.is_step {
+                effects.push("step lock".to_string());
+            }
+            if after.suit_limits != before.suit_limits && !after.suit_limits.is_empty() {
+                let suits = Suit::suits()
+                    .into_iter()
+                    .filter(|s| after.suit_limits.contains(s))
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>();
+                effects.push(format!("suits locked: {}", suits.join(", ")));
+            }
+        }
+        Phase::Suppression => {
+            if after.effect_limits.len() > before.effect_limits.len() {
+                effects.push("effects disabled".to_string());
+            }
+        }
+        Phase::Prompts => {
+            if after.prompts.len() > before.prompts.len() {
+                let questions = after.prompts[before.prompts.len()..].iter();
+                effects.extend(questions.map(|p| p.question.clone()));
+            }
+        }
+        Phase::Turn => {
+            let is_cut = after.river.is_empty() && !before.river.is_empty();
+            if is_cut {
+                effects.push("river cut".to_string());
+            }
+            if after.direction != before.direction {
+                effects.push("direction reversed".to_string());
+            }
+            // the leader of a cut is already chosen
+            match after.skips(combo) {
+                _ if is_cut => {}
+                0 => effects.push("serve again".to_string()),
+                1 => {}
+                n => effects.push(format!("skip {} players", n - 1)),
+            }
+        }
+    }
+    effects
+}
+
+/// opens a prompt to select `n` cards from the field of `kind`, or up to `n` if `up_to`.
+/// when only one answer is legal, it is applied without a prompt
+pub(crate) fn open_select_prompt(
+    game: &mut Game,
+    kind: PromptKind,
+    player_id: &str,
+    n: usize,
+    target: FieldKey,
+    up_to: bool,
+) -> Result<()> {
+    let source = kind.field(player_id);
+    let candidates = game.field(&source)?.0.clone();
+    let max = n.min(candidates.len());
+    let min = if up_to { 0 } else { max };
+    if max == 0 {
+        return Ok(());
+    }
+    let from = match &source {
+        FieldKey::Hands(_) => "hands".to_string(),
+        field => field.to_string(),
+    };
+    let question = if min == max {
+        format!("select {} cards from {}", max, from)
+    } else {
+        format!("select up to {} cards from {}", max, from)
+    };
+    let prompt = Prompt::new(kind, vec![player_id.to_string()], &question, &["ok"])
+        .with_count(min, max)
+        .with_candidates(HashMap::from([(player_id.to_string(), candidates.clone())]))
+        .with_fields(Some(source.clone()), Some(target));
+    if min == candidates.len() {
+        // every candidate must be selected
+        game.select_many(player_id, &source, candidates)?;
+        answer_handler(&prompt)?.on(player_id.to_string(), game)?;
+        game.clear_selects(player_id);
+        return Ok(());
+    }
+    game.open_prompt(prompt);
+    Ok(())
+}
+
+pub struct NoEffect;
+
+impl CardEffect for NoEffect {}
+
+/// 3: disables every effect until the river is reset
+pub struct SuppressAll;
+
+impl CardEffect for SuppressAll {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        _player_id: &str,
+        _combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Suppression {
+            return Ok(());
+        }
+        game.effect_limits.extend(1..=13);
+        Ok(())
+    }
+}
+
+/// 4: retrieves as many cards as served from trushes
+pub struct RetrieveTrushes;
+
+impl CardEffect for RetrieveTrushes {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        player_id: &str,
+        combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Prompts {
+            return Ok(());
+        }
+        let target = FieldKey::Hands(player_id.to_string());
+        if game.field(&target)?.0.is_empty() {
+            return Ok(());
+        }
+        let up_to = game.rules.select_up_to;
+        open_select_prompt(
+            game,
+            PromptKind::Select4,
+            player_id,
+            combo.size,
+            target,
+            up_to,
+        )
+    }
+}
+
+/// 5: skips as many players as served
+pub struct SkipPlayers;
+
+impl CardEffect for SkipPlayers {
+    fn on_end_turn(&self, _game: &Game, combo: &Combo) -> i32 {
+        combo.size as i32 + 1
+    }
+}
+
+/// 7: passes as many cards as served to the previous or next player in the direction of play
+pub struct PassCards {
+    pub target: PassTarget,
+}
+
+impl CardEffect for PassCards {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        player_id: &str,
+        combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Prompts {
+            return Ok(());
+        }
+        if game
+            .field(&FieldKey::Hands(player_id.to_string()))?
+            .0
+            .is_empty()
+        {
+            return Ok(());
+        }
+        let (kind, d) = match self.target {
+            PassTarget::Previous => (PromptKind::Select7, -1),
+            PassTarget::Next => (PromptKind::Select7Next, 1),
+        };
+        let target = FieldKey::Hands(game.next_player(player_id, d));
+        let up_to = game.rules.select_up_to;
+        open_select_prompt(game, kind, player_id, combo.size, target, up_to)
+    }
+}
+
+/// 8: the river is flushed at once and the server leads
+pub struct EightCut {
+    /// only a single 8 cuts
+    pub single_only: bool,
+}
+
+impl EightCut {
+    fn cuts(&self, combo: &Combo) -> bool {
+        !self.single_only || combo.size == 1
+    }
+}
+
+impl CardEffect for EightCut {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        player_id: &str,
+        combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Turn || !self.cuts(combo) {
+            return Ok(());
+        }
+        let to = self.on_flush(game, combo);
+        game.cut(player_id, &to)
+    }
+
+    fn on_end_turn(&self, _game: &Game, combo: &Combo) -> i32 {
+        if self.cuts(combo) {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// 9: the river size flips between 1 and 3
+pub struct FlipRiverSize;
+
+impl CardEffect for FlipRiverSize {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        _player_id: &str,
+        _combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Size {
+            return Ok(());
+        }
+        game.river_size = match game.river_size {
+            Some(1) => Some(3),
+            Some(3) => Some(1),
+            n => n,
+        };
+        Ok(())
+    }
+}
+
+/// 10: disables effects of 1 to 9 until the river is reset
+pub struct SuppressBelowTen;
+
+impl CardEffect for SuppressBelowTen {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        _player_id: &str,
+        _combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Suppression {
+            return Ok(());
+        }
+        game.effect_limits.extend(1..10);
+        Ok(())
+    }
+}
+
+/// 10: discards up to as many cards as served to trushes
+pub struct DiscardCards;
+
+impl CardEffect for DiscardCards {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        player_id: &str,
+        combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Prompts {
+            return Ok(());
+        }
+        let target = FieldKey::Trushes;
+        open_select_prompt(
+            game,
+            PromptKind::Select10,
+            player_id,
+            combo.size,
+            target,
+            true,
+        )
+    }
+}
+
+/// 11: card strength is reversed until the river is reset
+pub struct ElevenBack;
+
+impl CardEffect for ElevenBack {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        _player_id: &str,
+        _combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Strength {
+            return Ok(());
+        }
+        game.turn_revoluted = true;
+        Ok(())
+    }
+}
+
+/// 12: locks the river to steps in the served suits
+pub struct StepLock;
+
+impl CardEffect for StepLock {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        _player_id: &str,
+        combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Locks {
+            return Ok(());
+        }
+        game.is_step = true;
+        game.suit_limits = combo.suits.clone();
+        Ok(())
+    }
+}
+
+/// 13: retrieves as many cards as served from excluded
+pub struct RetrieveExcluded;
+
+impl CardEffect for RetrieveExcluded {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        player_id: &str,
+        combo: &Combo,
+    ) -> Result<()> {
+        if phase != Phase::Prompts {
+            return Ok(());
+        }
+        let target = FieldKey::Hands(player_id.to_string());
+        if game.field(&target)?.0.is_empty() {
+            return Ok(());
+        }
+        let up_to = game.rules.select_up_to;
+        open_select_prompt(
+            game,
+            PromptKind::Select13,
+            player_id,
+            combo.size,
+            target,
+            up_to,
+        )
+    }
+}
+
+/// A: the server moves again
+pub struct ServeAgain;
+
+impl CardEffect for ServeAgain {
+    fn on_end_turn(&self, _game: &Game, _combo: &Combo) -> i32 {
+        0
+    }
+}
+
+/// 2: the river is flushed to excluded
+pub struct Exclude;
+
+impl CardEffect for Exclude {
+    fn on_flush(&self, _game: &Game, _combo: &Combo) -> FieldKey {
+        FieldKey::Excluded
+    }
+}
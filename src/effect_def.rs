@@ -0,0 +1,332 @@
+use crate::{
+    card::Suit,
+    combo::{Combo, ComboKind},
+    effect::{open_select_prompt, CardEffect, PassCards, Phase},
+    game::{FieldKey, Game, PromptKind},
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// local rule written as data, loaded with the rules of the game
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EffectDef {
+    #[serde(default)]
+    pub trigger: Trigger,
+    /// all must hold when the serve resolves
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<Action>,
+}
+
+/// combos the definition applies to, unset fields match anything.
+/// a definition with `rank` replaces the built-in effect of that number,
+/// one without applies on top of every number
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Trigger {
+    pub rank: Option<u8>,
+    pub size: Option<usize>,
+    /// the combo contains this suit
+    pub suit: Option<Suit>,
+    pub kind: Option<ComboKind>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "name", content = "value")]
+pub enum Condition {
+    /// `revoluted` equals the value
+    Revoluted(bool),
+    /// `revoluted ^ turn_revoluted` equals the value
+    Reversed(bool),
+    /// the number is in `effect_limits`
+    Limited(u8),
+    /// the number is not in `effect_limits`
+    NotLimited(u8),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "name", content = "value")]
+pub enum Action {
+    /// skips the given number of players
+    Skip(u8),
+    /// skips one player per served card
+    SkipPerCard,
+    /// the server moves again
+    ServeAgain,
+    /// the river is flushed to `trushes` or `excluded`
+    FlushTo(FieldKey),
+    /// the server retrieves as many cards as served from `trushes` or `excluded`
+    Select(FieldKey),
+    /// the server passes as many cards as served to the player `rules.seven_pass` names
+    Pass,
+    /// turns go around the other way, e.g. for 9-reverse or Q-reverse
+    ReverseDirection,
+    /// locks the river to the served suits
+    SuitLock,
+    /// locks the river to steps
+    StepLock,
+    /// card strength is reversed until the river is reset
+    Reverse,
+    /// the river accepts combos of the given size
+    RiverSize(usize),
+    /// disables effects of the given numbers until the river is reset
+    Suppress(Vec<u8>),
+}
+
+impl Action {
+    /// phase the action resolves in
+    pub fn phase(&self) -> Phase {
+        match self {
+            Action::RiverSize(_) => Phase::Size,
+            Action::Reverse => Phase::Strength,
+            Action::SuitLock | Action::StepLock => Phase::Locks,
+            Action::Suppress(_) => Phase::Suppression,
+            Action::Select(_) | Action::Pass => Phase::Prompts,
+            Action::Skip(_)
+            | Action::SkipPerCard
+            | Action::ServeAgain
+            | Action::FlushTo(_)
+            | Action::ReverseDirection => Phase::Turn,
+        }
+    }
+}
+
+impl EffectDef {
+    pub fn validate(&self) -> Result<()> {
+        let is_number = |n: &u8| (1..=13).contains(n);
+        if let Some(n) = self.trigger.rank.filter(|n| !is_number(n)) {
+            return Err(anyhow!("invalid trigger rank {}", n));
+        }
+        if self.actions.is_empty() {
+            return Err(anyhow!("effect has no action"));
+        }
+        for condition in self.conditions.iter() {
+            if let Condition::Limited(n) | Condition::NotLimited(n) = condition {
+                if !is_number(n) {
+                    return Err(anyhow!("invalid condition number {}", n));
+                }
+            }
+        }
+        for action in self.actions.iter() {
+            match action {
+                Action::FlushTo(FieldKey::Hands(_)) | Action::Select(FieldKey::Hands(_)) => {
+                    return Err(anyhow!("hands cannot be used in {:?}", action));
+                }
+                Action::RiverSize(0) => return Err(anyhow!("invalid river size 0")),
+                Action::Suppress(ns) => {
+                    if let Some(n) = ns.iter().find(|n| !is_number(n)) {
+                        return Err(anyhow!("invalid suppressed number {}", n));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, game: &Game, combo: &Combo) -> bool {
+        // rank is matched by the registry against the effect number
+        let trigger = &self.trigger;
+        if trigger.size.is_some_and(|n| combo.size != n) {
+            return false;
+        }
+        if trigger
+            .suit
+            .as_ref()
+            .is_some_and(|s| !combo.suits.contains(s))
+        {
+            return false;
+        }
+        if trigger.kind.as_ref().is_some_and(|k| &combo.kind != k) {
+            return false;
+        }
+        self.conditions.iter().all(|c| match c {
+            Condition::Revoluted(b) => game.revoluted == *b,
+            Condition::Reversed(b) => game.strength().reversed == *b,
+            Condition::Limited(n) => game.effect_limits.contains(n),
+            Condition::NotLimited(n) => !game.effect_limits.contains(n),
+        })
+    }
+}
+
+/// effect of a number with definitions applied on top of `base`,
+/// `base` is `None` when a definition replaces the built-in effect
+pub struct DefinedEffect {
+    pub base: Option<Box<dyn CardEffect>>,
+    /// definitions with their index in `rules.effects`
+    pub defs: Vec<(usize, EffectDef)>,
+}
+
+impl DefinedEffect {
+    /// actions of the definitions in `game.matched_effects`, which are matched once
+    /// before the first phase so earlier phases do not change later ones
+    fn actions<'a>(&'a self, game: &'a Game) -> impl Iterator<Item = &'a Action> {
+        self.defs
+            .iter()
+            .filter(move |(i, _)| game.matched_effects.contains(i))
+            .flat_map(|(_, d)| d.actions.iter())
+    }
+}
+
+impl CardEffect for DefinedEffect {
+    fn on_serve(
+        &self,
+        phase: Phase,
+        game: &mut Game,
+        player_id: &str,
+        combo: &Combo,
+    ) -> Result<()> {
+        if let Some(base) = &self.base {
+            base.on_serve(phase, game, player_id, combo)?;
+        }
+        let actions = self
+            .actions(game)
+            .filter(|a| a.phase() == phase)
+            .cloned()
+            .collect::<Vec<_>>();
+        for action in actions {
+            match action {
+                Action::Select(from) => {
+                    let kind = if from == FieldKey::Excluded {
+                        PromptKind::Select13
+                    } else {
+                        PromptKind::Select4
+                    };
+                    let target = FieldKey::Hands(player_id.to_string());
+                    if game.field(&target)?.0.is_empty() {
+                        continue;
+                    }
+                    let up_to = game.rules.select_up_to;
+                    open_select_prompt(game, kind, player_id, combo.size, target, up_to)?;
+                }
+                Action::Pass => {
+                    let target = game.rules.seven_pass.clone();
+                    PassCards { target }.on_serve(phase, game, player_id, combo)?;
+                }
+                Action::SuitLock => game.suit_limits = combo.suits.clone(),
+                Action::StepLock => game.is_step = true,
+                Action::Reverse => game.turn_revoluted = !game.turn_revoluted,
+                Action::RiverSize(n) => game.river_size = Some(n),
+                Action::Suppress(ns) => game.effect_limits.extend(ns),
+                Action::ReverseDirection => game.direction = game.direction.reversed(),
+                // skips and the flush target are read at the end of the turn
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn on_end_turn(&self, game: &Game, combo: &Combo) -> i32 {
+        let skips = self.actions(game).fold(None, |skips, action| match action {
+            Action::Skip(n) => Some(*n as i32 + 1),
+            Action::SkipPerCard => Some(combo.size as i32 + 1),
+            Action::ServeAgain => Some(0),
+            _ => skips,
+        });
+        match (skips, &self.base) {
+            (Some(skips), _) => skips,
+            (None, Some(base)) => base.on_end_turn(game, combo),
+            (None, None) => 1,
+        }
+    }
+
+    fn on_flush(&self, game: &Game, combo: &Combo) -> FieldKey {
+        let to = self.actions(game).fold(None, |to, action| match action {
+            Action::FlushTo(field) => Some(field.clone()),
+            _ => to,
+        });
+        match (to, &self.base) {
+            (Some(to), _) => to,
+            (None, Some(base)) => base.on_flush(game, combo),
+            (None, None) => FieldKey::Trushes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::Suit,
+        game::{Direction, FieldKey},
+        rules::Rules,
+        testing::{cards, pass, serve, setup},
+    };
+    use std::collections::HashSet;
+
+    fn rules(effects: &str) -> Rules {
+        let json = format!(r#"{{"interrupts":[],"effects":{}}}"#, effects);
+        let rules: Rules = serde_json::from_str(&json).unwrap();
+        rules.validate().unwrap();
+        rules
+    }
+
+    fn invalid(effects: &str) -> bool {
+        let json = format!(r#"{{"effects":{}}}"#, effects);
+        let rules: Rules = serde_json::from_str(&json).unwrap();
+        rules.validate().is_err()
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        assert!(invalid(
+            r#"[{"trigger":{"rank":14},"actions":[{"name":"ServeAgain"}]}]"#
+        ));
+        assert!(invalid(r#"[{"trigger":{"rank":5},"actions":[]}]"#));
+        assert!(invalid(
+            r#"[{"actions":[{"name":"FlushTo","value":"hands:p0"}]}]"#
+        ));
+        assert!(invalid(r#"[{"actions":[{"name":"RiverSize","value":0}]}]"#));
+        assert!(invalid(
+            r#"[{"actions":[{"name":"Suppress","value":[3,14]}]}]"#
+        ));
+        assert!(invalid(
+            r#"[{"conditions":[{"name":"Limited","value":0}],"actions":[{"name":"ServeAgain"}]}]"#
+        ));
+        assert!(!invalid(
+            r#"[{"trigger":{"rank":12},"actions":[{"name":"Reverse"}]}]"#
+        ));
+    }
+
+    #[test]
+    fn ranked_definition_replaces_the_builtin_effect() {
+        let rules = rules(r#"[{"trigger":{"rank":12},"actions":[{"name":"ReverseDirection"}]}]"#);
+        let mut game = setup(&["Qs 6s", "4h 3h", "4d 3d", "4c 3c"], rules);
+        serve(&mut game, "p0", "Qs").unwrap();
+        assert_eq!(game.direction, Direction::Backward);
+        // the built-in step lock of 12 is gone
+        assert!(!game.is_step);
+        assert_eq!(game.current.as_deref(), Some("p3"));
+    }
+
+    #[test]
+    fn skip_action_overrides_the_builtin_turn() {
+        let rules = rules(r#"[{"trigger":{"rank":9},"actions":[{"name":"Skip","value":1}]}]"#);
+        let mut game = setup(&["9s 6s", "4h 3h", "4d 3d", "4c 3c"], rules);
+        serve(&mut game, "p0", "9s").unwrap();
+        // no size flip, one player skipped
+        assert_eq!(game.river_size, Some(1));
+        assert_eq!(game.current.as_deref(), Some("p2"));
+    }
+
+    #[test]
+    fn unranked_definition_applies_on_top_of_the_builtin_effect() {
+        let rules = rules(
+            r#"[{"trigger":{"suit":"h"},"conditions":[{"name":"Revoluted","value":false}],
+                "actions":[{"name":"SuitLock"},{"name":"FlushTo","value":"excluded"}]}]"#,
+        );
+        let mut game = setup(&["5h 6s", "4h 3h", "4d 3d", "4c 3c"], rules.clone());
+        serve(&mut game, "p0", "5h").unwrap();
+        assert_eq!(game.suit_limits, HashSet::from([Suit::Heart]));
+        // the built-in 5 still skips
+        assert_eq!(game.current.as_deref(), Some("p2"));
+        pass(&mut game, "p2").unwrap();
+        pass(&mut game, "p3").unwrap();
+        assert!(game.river.is_empty());
+        assert_eq!(game.fields[&FieldKey::Excluded].0, cards("5h"));
+
+        let mut game = setup(&["5h 6s", "4h 3h", "4d 3d", "4c 3c"], rules);
+        game.revoluted = true;
+        serve(&mut game, "p0", "5h").unwrap();
+        assert!(game.suit_limits.is_empty());
+    }
+}
@@ -1,11 +1,10 @@
 use super::{effect_card::EffectCard, Event, EventHandler};
 use crate::{
-    card::{card_ord, number},
-    game::{FieldKey, Game, PromptKind},
+    game::{FieldKey, Game, Prompt, PromptKind},
+    interrupt::{serve_counter, InterruptKind},
 };
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Answer {
@@ -14,54 +13,101 @@ pub struct Answer {
 
 impl EventHandler for Answer {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let Some(prompt) = game.prompt.last().cloned() else {
+        let Some(prompt) = game.prompt().cloned() else {
             return Err(anyhow!("no prompt"));
         };
+        if !prompt.pending_player_ids().contains(&player_id) {
+            return Err(anyhow!("not asked to answer"));
+        }
+        if !prompt.options.contains(&self.answer) {
+            return Err(anyhow!("invalid option {}", self.answer));
+        }
         // validate answer
         let validate: Box<dyn EventHandler> = match prompt.kind {
             PromptKind::Select4 => Box::new(ValidatePromptSelect4),
-            PromptKind::Select7 => Box::new(ValidatePromptSelect7),
+            PromptKind::Select7 | PromptKind::Select7Next => Box::new(ValidatePromptSelect7 {
+                kind: prompt.kind.clone(),
+            }),
+            PromptKind::Select10 => Box::new(ValidatePromptSelect10),
             PromptKind::Select13 => Box::new(ValidatePromptSelect13),
-            PromptKind::UseOneChance => Box::new(ValidatePromptSelectOneChance {
+            PromptKind::Interrupt(_) => Box::new(ValidatePromptInterrupt {
                 answer: self.answer.clone(),
             }),
         };
         validate.on(player_id.to_string(), game)?;
-        game.answers
+        let selection = game.selected(&player_id, &prompt.kind.field(&player_id));
+        let prompt = game.prompt_mut().unwrap();
+        prompt
+            .answers
             .insert(player_id.to_string(), self.answer.clone());
+        prompt.selections.insert(player_id.to_string(), selection);
 
-        // check all players answers
-        let all_answered = prompt.player_ids.iter().collect::<HashSet<_>>()
-            == game.answers.keys().collect::<HashSet<_>>();
-        if all_answered {
-            game.answers.clear();
-            let answer_prompt: Box<dyn EventHandler> = match prompt.kind {
-                PromptKind::Select4 => Box::new(AnswerPromptSelect4),
-                PromptKind::Select7 => Box::new(AnswerPromptSelect7),
-                PromptKind::Select13 => Box::new(AnswerPromptSelect13),
-                PromptKind::UseOneChance => Box::new(AnswerPromptSelectOneChance),
-            };
-            answer_prompt.on(player_id.to_string(), game)?;
+        if prompt.is_answered() {
+            // answers may open another prompt on top of the stack
+            let prompt = game.resolve_prompt()?;
+            answer_handler(&prompt)?.on(player_id.to_string(), game)?;
 
             // reset select
-            game.selects.insert(player_id.to_string(), vec![]);
+            for id in prompt.player_ids.iter() {
+                game.clear_selects(id);
+            }
 
-            game.last_served_player_id = Some(player_id.to_string());
-            game.on_end_turn()?;
+            if game.prompts.is_empty() {
+                game.on_end_serve()?;
+            }
         }
         Ok(Event::None)
     }
 }
 
+/// applies the answers of a resolved prompt
+pub fn answer_handler(prompt: &Prompt) -> Result<Box<dyn EventHandler>> {
+    let handler: Box<dyn EventHandler> = match &prompt.kind {
+        PromptKind::Select4 => Box::new(AnswerPromptSelect4),
+        PromptKind::Select7 | PromptKind::Select7Next => Box::new(AnswerPromptSelect7 {
+            kind: prompt.kind.clone(),
+            target: prompt.target.clone().ok_or(anyhow!("no target"))?,
+        }),
+        PromptKind::Select10 => Box::new(AnswerPromptSelect10),
+        PromptKind::Select13 => Box::new(AnswerPromptSelect13),
+        PromptKind::Interrupt(kind) => Box::new(AnswerPromptInterrupt {
+            kind: kind.clone(),
+            prompt: prompt.clone(),
+        }),
+    };
+    Ok(handler)
+}
+
+/// selected cards must be candidates of the active prompt within its bounds
+fn validate_candidates(player_id: &str, game: &Game, kind: PromptKind, from: &str) -> Result<()> {
+    let prompt = game.prompt().ok_or(anyhow!("no prompt"))?;
+    let selects = game.selected(player_id, &kind.field(player_id));
+    let candidates = prompt.candidates(player_id);
+    if let Some(card) = selects.iter().find(|c| !candidates.contains(c)) {
+        return Err(anyhow!("{} cannot be selected", card));
+    }
+    let n_cards = selects.len();
+    if (prompt.min..=prompt.max).contains(&n_cards) {
+        return Ok(());
+    }
+    if prompt.min == prompt.max {
+        Err(anyhow!("please select {} cards in {}", prompt.max, from))
+    } else {
+        Err(anyhow!(
+            "please select {} to {} cards in {}",
+            prompt.min,
+            prompt.max,
+            from
+        ))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidatePromptSelect4;
 
 impl EventHandler for ValidatePromptSelect4 {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let n_cards = game.river.last().unwrap().len();
-        if game.selects.get(&player_id).unwrap().len() != n_cards {
-            return Err(anyhow!("please select {} cards in trushes", n_cards));
-        }
+        validate_candidates(&player_id, game, PromptKind::Select4, "trushes")?;
         Ok(Event::None)
     }
 }
@@ -71,43 +117,68 @@ pub struct AnswerPromptSelect4;
 
 impl EventHandler for AnswerPromptSelect4 {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let cards = game.selects.get(&player_id).unwrap().clone();
+        let cards = game.selected(&player_id, &PromptKind::Select4.field(&player_id));
         game.transfer(
             &FieldKey::Trushes,
             &FieldKey::Hands(player_id.clone()),
             cards,
         )?;
-        game.field_mut(&FieldKey::Hands(player_id))?.sort(card_ord);
+        let strength = game.strength();
+        strength.sort(game.field_mut(&FieldKey::Hands(player_id))?);
         Ok(Event::None)
     }
 }
 
+/// passes cards to a neighbour, `kind` is `Select7` or `Select7Next`
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ValidatePromptSelect7;
+pub struct ValidatePromptSelect7 {
+    pub kind: PromptKind,
+}
 
 impl EventHandler for ValidatePromptSelect7 {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let n_cards = game.river.last().unwrap().len();
-        if game.selects.get(&player_id).unwrap().len() != n_cards {
-            return Err(anyhow!("please select {} cards in hands", n_cards));
-        }
+        validate_candidates(&player_id, game, self.kind.clone(), "hands")?;
         Ok(Event::None)
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct AnswerPromptSelect7;
+pub struct AnswerPromptSelect7 {
+    pub kind: PromptKind,
+    pub target: FieldKey,
+}
 
 impl EventHandler for AnswerPromptSelect7 {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let cards = game.selects.get(&player_id).unwrap().clone();
-        let passer: String = game.get_relative_player(&player_id, -1);
+        let cards = game.selected(&player_id, &self.kind.field(&player_id));
+        game.transfer(&FieldKey::Hands(player_id.to_string()), &self.target, cards)?;
+        let strength = game.strength();
+        strength.sort(game.field_mut(&self.target)?);
+        Ok(Event::None)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidatePromptSelect10;
+
+impl EventHandler for ValidatePromptSelect10 {
+    fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
+        validate_candidates(&player_id, game, PromptKind::Select10, "hands")?;
+        Ok(Event::None)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnswerPromptSelect10;
+
+impl EventHandler for AnswerPromptSelect10 {
+    fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
+        let cards = game.selected(&player_id, &PromptKind::Select10.field(&player_id));
         game.transfer(
             &FieldKey::Hands(player_id.to_string()),
-            &FieldKey::Hands(passer.clone()),
+            &FieldKey::Trushes,
             cards,
         )?;
-        game.field_mut(&FieldKey::Hands(passer))?.sort(card_ord);
         Ok(Event::None)
     }
 }
@@ -117,10 +188,7 @@ pub struct ValidatePromptSelect13;
 
 impl EventHandler for ValidatePromptSelect13 {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let n_cards = game.river.last().unwrap().len();
-        if game.selects.get(&player_id).unwrap().len() != n_cards {
-            return Err(anyhow!("please select {} cards in excluded", n_cards));
-        }
+        validate_candidates(&player_id, game, PromptKind::Select13, "excluded")?;
         Ok(Event::None)
     }
 }
@@ -130,46 +198,143 @@ pub struct AnswerPromptSelect13;
 
 impl EventHandler for AnswerPromptSelect13 {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let cards = game.selects.get(&player_id).unwrap().clone();
+        let cards = game.selected(&player_id, &PromptKind::Select13.field(&player_id));
 
         game.transfer(
             &FieldKey::Excluded,
             &FieldKey::Hands(player_id.clone()),
             cards,
         )?;
-        game.field_mut(&FieldKey::Hands(player_id.to_string()))?
-            .sort(card_ord);
+        let strength = game.strength();
+        strength.sort(game.field_mut(&FieldKey::Hands(player_id.to_string()))?);
         Ok(Event::None)
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ValidatePromptSelectOneChance {
+pub struct ValidatePromptInterrupt {
     answer: String,
 }
 
-impl EventHandler for ValidatePromptSelectOneChance {
+impl EventHandler for ValidatePromptInterrupt {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let serves = game.selects.get(&player_id).unwrap().clone();
-        if self.answer == *"serve" && (serves.len() != 1 || number(&serves) != 1) {
-            return Err(anyhow!("please select A"));
+        let serves = game.selected(&player_id, &FieldKey::Hands(player_id.clone()));
+        let candidates = game
+            .prompt()
+            .map(|p| p.candidates(&player_id))
+            .unwrap_or_default();
+        if self.answer == *"serve" && (serves.len() != 1 || !candidates.contains(&serves[0])) {
+            let candidates = candidates.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            return Err(anyhow!("please select one of {}", candidates.join(", ")));
         }
         Ok(Event::None)
     }
 }
 
+/// the nearest responder after the server wins and serves the counter,
+/// other responders keep their cards
 #[derive(Debug, Serialize, Deserialize)]
-pub struct AnswerPromptSelectOneChance;
+pub struct AnswerPromptInterrupt {
+    pub kind: InterruptKind,
+    pub prompt: Prompt,
+}
 
-impl EventHandler for AnswerPromptSelectOneChance {
-    fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let serves = game
+impl EventHandler for AnswerPromptInterrupt {
+    fn on(&self, _player_id: String, game: &mut Game) -> Result<Event> {
+        let combo = game
             .river
             .last()
             .cloned()
-            .expect("river is empty on UseOneChance");
-        let event = EffectCard { serves };
-        event.on(player_id, game)?;
+            .expect("river is empty on Interrupt");
+        let server = combo.server.clone();
+        let winner = game
+            .seats_after(&server)
+            .into_iter()
+            .find(|id| self.prompt.answers.get(id).map(|a| a.as_str()) == Some("serve"));
+        let Some(winner) = winner else {
+            let event = EffectCard { combo };
+            event.on(server, game)?;
+            return Ok(Event::None);
+        };
+
+        let counter = serve_counter(game, &winner, &self.prompt)?;
+        // the countered serve still resolves, then the counter
+        let event = EffectCard { combo };
+        event.on(server, game)?;
+        let event = EffectCard { combo: counter };
+        event.on(winner.clone(), game)?;
+        if self.kind.interrupt().cuts() {
+            game.cut(&winner, &FieldKey::Trushes)?;
+        }
         Ok(Event::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::{Direction, FieldKey, Game},
+        interrupt::InterruptKind,
+        rules::Rules,
+        testing::{answer, cards, select, serve, setup},
+    };
+
+    fn one_chance(direction: Direction) -> Game {
+        let rules = Rules {
+            interrupts: vec![InterruptKind::OneChance],
+            ..Rules::default()
+        };
+        let mut game = setup(&["6s Ks", "4h 5h", "Ah 5d", "Ac 5c"], rules);
+        game.direction = direction;
+        serve(&mut game, "p0", "6s").unwrap();
+        game
+    }
+
+    fn hand(game: &Game, player_id: &str) -> Vec<crate::card::Card> {
+        game.fields[&FieldKey::Hands(player_id.to_string())]
+            .0
+            .clone()
+    }
+
+    #[test]
+    fn nearest_responder_after_the_server_wins() {
+        let mut game = one_chance(Direction::Forward);
+        let prompt = game.prompt().unwrap();
+        assert_eq!(prompt.player_ids, vec!["p2", "p3"]);
+        answer(&mut game, "p3", "serve", "Ac").unwrap();
+        answer(&mut game, "p2", "serve", "Ah").unwrap();
+
+        assert!(game.prompts.is_empty());
+        assert!(game.river.is_empty());
+        assert_eq!(game.current.as_deref(), Some("p2"));
+        assert_eq!(hand(&game, "p2"), cards("5d"));
+        assert_eq!(hand(&game, "p3"), cards("Ac 5c"));
+    }
+
+    #[test]
+    fn responders_are_ordered_in_the_direction_of_play() {
+        let mut game = one_chance(Direction::Backward);
+        let prompt = game.prompt().unwrap();
+        assert_eq!(prompt.player_ids, vec!["p3", "p2"]);
+        answer(&mut game, "p2", "serve", "Ah").unwrap();
+        answer(&mut game, "p3", "serve", "Ac").unwrap();
+
+        assert_eq!(game.current.as_deref(), Some("p3"));
+        assert_eq!(hand(&game, "p2"), cards("Ah 5d"));
+        assert_eq!(hand(&game, "p3"), cards("5c"));
+    }
+
+    #[test]
+    fn counter_is_the_card_selected_when_answering() {
+        let mut game = one_chance(Direction::Forward);
+        answer(&mut game, "p2", "serve", "Ah").unwrap();
+        // selecting after answering does not change the counter
+        let field = FieldKey::Hands("p2".to_string());
+        game.clear_selects("p2");
+        select(&mut game, "p2", field, "5d").unwrap();
+        answer(&mut game, "p3", "skip", "").unwrap();
+
+        assert_eq!(game.current.as_deref(), Some("p2"));
+        assert_eq!(hand(&game, "p2"), cards("5d"));
+    }
+}
@@ -1,8 +1,7 @@
 use super::{Event, EventHandler};
 use crate::{
-    card::card_ord,
     deck::Deck,
-    game::{FieldKey, Game},
+    game::{Direction, FieldKey, Game},
 };
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
@@ -15,15 +14,20 @@ impl EventHandler for Distribute {
         if game.players.is_empty() {
             return Err(anyhow!("players is empty"));
         }
+        // a new deal drops prompts of the previous one
+        while game.cancel_prompt().is_ok() {}
+
         let mut deck = Deck::all(2);
         deck.shuffle();
         let mut decks = deck.split(game.players.len())?;
+        let strength = game.strength();
         for (i, player_id) in game.players.iter().enumerate() {
-            decks[i].sort(card_ord);
+            strength.sort(&mut decks[i]);
             game.fields
                 .insert(FieldKey::Hands(player_id.to_string()), decks[i].clone());
         }
         game.current = Some(game.players[0].clone());
+        game.direction = Direction::Forward;
         Ok(Event::None)
     }
 }
@@ -1,99 +1,153 @@
 use super::{Event, EventHandler};
 use crate::{
-    card::{number, suits, Card},
-    game::{FieldKey, Game, Prompt, PromptKind},
+    combo::Combo,
+    effect::{describe, Phase, Resolution},
+    game::Game,
 };
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+/// resolves `combo` phase by phase, see `Phase::order`
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EffectCard {
-    pub serves: Vec<Card>,
+    pub combo: Combo,
 }
 
 impl EventHandler for EffectCard {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        let serves = self.serves.clone();
-        game.river_size = Some(serves.len());
+        // the effect is decided before any phase, so a 3 does not disable itself
+        let effects = game.effects();
+        let effect = game.effect_number(&self.combo).and_then(|n| effects.get(n));
+        // and so are the conditions of defined effects, see `DefinedEffect`
+        game.matched_effects = game
+            .rules
+            .effects
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.matches(game, &self.combo))
+            .map(|(i, _)| i)
+            .collect();
 
-        if serves.len() == 4 {
-            game.revoluted = !game.revoluted;
-        }
-
-        game.river_size = Some(serves.len());
-
-        let n = number(&serves);
-        if game.effect_limits.contains(&n) {
-            return Ok(Event::None);
-        }
-
-        let hands = game.field(&FieldKey::Hands(player_id.clone()))?;
-        match n {
-            3 => game.effect_limits.extend(1..=13),
-            4 => {
-                let trushes = game.field(&FieldKey::Trushes)?;
-                if hands.0.is_empty() || trushes.0.is_empty() {
-                    return Ok(Event::None);
-                }
-                let prompt = Prompt {
-                    kind: PromptKind::Select4,
-                    player_ids: vec![player_id.to_string()],
-                    question: "select cards from trushes".to_string(),
-                    options: vec!["ok".to_string()],
-                };
-                game.prompt.push(prompt);
-            }
-            5 => {}
-            6 => {}
-            7 => {
-                if hands.0.is_empty() {
-                    return Ok(Event::None);
+        for phase in Phase::order() {
+            let before = game.clone();
+            match phase {
+                Phase::Size => game.river_size = Some(self.combo.size),
+                Phase::Strength if self.combo.size == 4 => game.revoluted = !game.revoluted,
+                Phase::Locks => {
+                    game.update_suit_lock(&self.combo);
+                    game.update_step_lock(&self.combo);
                 }
-                let prompt = Prompt {
-                    kind: PromptKind::Select7,
-                    player_ids: vec![player_id.to_string()],
-                    question: "select cards from hands".to_string(),
-                    options: vec!["ok".to_string()],
-                };
-                game.prompt.push(prompt);
-            }
-            8 => {}
-            9 => {
-                game.river_size = match game.river_size {
-                    Some(1) => Some(3),
-                    Some(3) => Some(1),
-                    n => n,
-                };
+                _ => {}
             }
-            10 => {
-                game.effect_limits.extend(1..10);
+            if let Some(effect) = effect {
+                effect.on_serve(phase, game, &player_id, &self.combo)?;
             }
-            11 => {
-                game.turn_revoluted = true;
+            if phase == Phase::Strength && game.strength().reversed != before.strength().reversed {
+                game.sort_hands();
             }
-            12 => {
-                game.is_step = true;
-                game.suit_limits = suits(&serves);
+            for description in describe(phase, &before, game, &self.combo) {
+                game.resolution.push(Resolution {
+                    phase,
+                    server: player_id.clone(),
+                    description,
+                });
             }
-            13 => {
-                let excluded = game.field(&FieldKey::Excluded)?;
-                if hands.0.is_empty() || excluded.0.is_empty() {
-                    return Ok(Event::None);
-                }
-                let prompt = Prompt {
-                    kind: PromptKind::Select13,
-                    player_ids: vec![player_id.to_string()],
-                    question: "select cards from excluded".to_string(),
-                    options: vec!["ok".to_string()],
-                };
-                game.prompt.push(prompt);
-            }
-            1 => {}
-            2 => {}
-            _ => {
-                return Err(anyhow!("invalid number {}", n));
-            }
-        };
+        }
         Ok(Event::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        effect::{Phase, Resolution},
+        events::serve::preview_serve,
+        game::{FieldKey, Game, PromptKind},
+        testing::{cards, no_interrupts, select, serve, setup},
+        validator::{violations, Violation},
+    };
+
+    fn phases(resolution: &[Resolution]) -> Vec<(Phase, &str)> {
+        resolution
+            .iter()
+            .map(|r| (r.phase, r.description.as_str()))
+            .collect()
+    }
+
+    fn four_fours() -> Game {
+        let mut game = setup(&["4s 4h 4d 4c 6s", "5h 6h"], no_interrupts());
+        game.fields
+            .insert(FieldKey::Trushes, cards("Qs Ks Js Ts 9s").into());
+        game
+    }
+
+    #[test]
+    fn revolution_resolves_before_the_prompt_opens() {
+        let mut game = four_fours();
+        serve(&mut game, "p0", "4s 4h 4d 4c").unwrap();
+        assert!(game.revoluted);
+        assert_eq!(game.prompt().unwrap().kind, PromptKind::Select4);
+        let resolution = phases(&game.resolution);
+        assert_eq!(resolution[0], (Phase::Strength, "revolution"));
+        assert_eq!(resolution[1].0, Phase::Prompts);
+        assert_eq!(resolution.len(), 2);
+    }
+
+    #[test]
+    fn preview_lists_the_same_resolution_as_the_serve() {
+        let mut game = four_fours();
+        let hands = FieldKey::Hands("p0".to_string());
+        select(&mut game, "p0", hands, "4s 4h 4d 4c").unwrap();
+        let preview = preview_serve("p0", &game);
+        assert!(preview.legal);
+        assert!(preview.revolution);
+        assert_eq!(preview.prompt, Some(PromptKind::Select4));
+        assert_eq!(preview.effects[0], "revolution");
+
+        game.clear_selects("p0");
+        serve(&mut game, "p0", "4s 4h 4d 4c").unwrap();
+        assert_eq!(phases(&preview.resolution), phases(&game.resolution));
+    }
+
+    #[test]
+    fn three_does_not_disable_itself() {
+        let mut game = setup(&["3s 6s", "5h 6h"], no_interrupts());
+        serve(&mut game, "p0", "3s").unwrap();
+        assert_eq!(game.effect_limits, (1..=13).collect());
+        assert_eq!(
+            phases(&game.resolution),
+            vec![(Phase::Suppression, "effects disabled")]
+        );
+    }
+
+    #[test]
+    fn nine_flips_the_size_the_next_serve_must_match() {
+        let mut game = setup(&["9s 6s", "Ks Kh Kd Qh"], no_interrupts());
+        serve(&mut game, "p0", "9s").unwrap();
+        assert_eq!(game.river_size, Some(3));
+        assert_eq!(
+            phases(&game.resolution),
+            vec![(Phase::Size, "river size becomes 3")]
+        );
+        let expected = Violation::SizeMismatch {
+            expected: 3,
+            actual: 1,
+        };
+        assert_eq!(violations(&game, "p1", &cards("Qh")), vec![expected]);
+        assert!(violations(&game, "p1", &cards("Ks Kh Kd")).is_empty());
+    }
+
+    #[test]
+    fn cut_is_not_reported_as_serve_again() {
+        let mut game = setup(&["8s 6s", "5h 6h"], no_interrupts());
+        let hands = FieldKey::Hands("p0".to_string());
+        select(&mut game, "p0", hands, "8s").unwrap();
+        let preview = preview_serve("p0", &game);
+        assert_eq!(preview.effects, vec!["river cut"]);
+
+        game.clear_selects("p0");
+        serve(&mut game, "p0", "8s").unwrap();
+        assert_eq!(phases(&game.resolution), vec![(Phase::Turn, "river cut")]);
+        assert_eq!(game.current.as_deref(), Some("p0"));
+    }
+}
@@ -1,6 +1,9 @@
-use crate::game::Game;
+use crate::game::{FieldKey, Game};
 use anyhow::Result;
 use extism_pdk::ToBytes;
+use serde_with::{
+    formats::CommaSeparator, serde_as, DisplayFromStr, PickFirst, StringWithSeparator,
+};
 
 pub mod answer;
 pub mod distribute;
@@ -9,14 +12,29 @@ pub mod pass;
 pub mod select;
 pub mod serve;
 
+#[serde_as]
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(tag = "name", content = "value")]
 pub enum Event {
     Distribute,
     Select {
-        field: String,
+        field: FieldKey,
         card: String,
     },
+    /// replaces the selection in `field` with `cards`, a list or comma separated
+    SelectMany {
+        field: FieldKey,
+        #[serde_as(as = "PickFirst<(_, StringWithSeparator<CommaSeparator, String>)>")]
+        cards: Vec<String>,
+    },
+    /// selects every card of `rank` in hands, and jokers if `jokers`
+    SelectRank {
+        rank: String,
+        #[serde(default)]
+        #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+        jokers: bool,
+    },
+    ClearSelection,
     Answer {
         option: String,
     },
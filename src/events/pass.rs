@@ -8,11 +8,7 @@ pub struct Pass;
 
 impl EventHandler for Pass {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        if let Some(prompt) = game.prompt.first() {
-            if prompt.player_ids.contains(&player_id) && !game.answers.contains_key(&player_id) {
-                return Err(anyhow!("please answer"));
-            }
-        }
+        game.check_blocked(&player_id)?;
 
         if game.current != Some(player_id.clone()) {
             return Err(anyhow!("not your turn"));
@@ -20,7 +16,8 @@ impl EventHandler for Pass {
         if game.river.is_empty() {
             return Err(anyhow!("cannot pass because river is empty"));
         }
-        game.on_end_turn()?;
+        game.passed_player_ids.insert(player_id);
+        game.on_end_turn(1)?;
         Ok(Event::None)
     }
 }
@@ -1,31 +1,73 @@
 use super::{Event, EventHandler};
-use crate::{card::Card, game::Game};
-use anyhow::Result;
+use crate::{
+    card::{number_str, Card},
+    game::{FieldError, FieldKey, Game},
+};
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Select {
-    pub field: String,
+    pub field: FieldKey,
     pub card: Card,
 }
 
 impl EventHandler for Select {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        if game.selects.get(&player_id).unwrap().contains(&self.card) {
-            let index = game
-                .selects
-                .get(&player_id)
-                .unwrap()
-                .iter()
-                .position(|c| c == &self.card)
-                .unwrap();
-            game.selects.get_mut(&player_id).unwrap().remove(index);
-        } else {
-            game.selects
-                .get_mut(&player_id)
-                .unwrap()
-                .push(self.card.clone());
+        if matches!(&self.field, FieldKey::Hands(id) if id != &player_id) {
+            return Err(FieldError::Foreign(self.field.clone()).into());
         }
+        game.toggle_select(&player_id, &self.field, self.card.clone())?;
+        Ok(Event::None)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectMany {
+    pub field: FieldKey,
+    pub cards: Vec<Card>,
+}
+
+impl EventHandler for SelectMany {
+    fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
+        if matches!(&self.field, FieldKey::Hands(id) if id != &player_id) {
+            return Err(FieldError::Foreign(self.field.clone()).into());
+        }
+        game.select_many(&player_id, &self.field, self.cards.clone())?;
+        Ok(Event::None)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectRank {
+    pub rank: u8,
+    pub jokers: bool,
+}
+
+impl EventHandler for SelectRank {
+    fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
+        let field = FieldKey::Hands(player_id.clone());
+        let hands = game.field(&field)?;
+        if !hands.0.iter().any(|c| c.number() == Some(self.rank)) {
+            return Err(anyhow!("no {} in hands", number_str(self.rank)));
+        }
+        let cards = hands
+            .0
+            .iter()
+            .filter(|c| c.number() == Some(self.rank) || (self.jokers && c.number().is_none()))
+            .cloned()
+            .collect();
+        game.select_many(&player_id, &field, cards)?;
+        Ok(Event::None)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClearSelection;
+
+impl EventHandler for ClearSelection {
+    fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
+        game.clear_selects(&player_id);
         Ok(Event::None)
     }
 }
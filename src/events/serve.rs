@@ -1,65 +1,116 @@
 use super::{effect_card::EffectCard, Event, EventHandler};
 use crate::{
-    card::{cardinal, is_same_number, match_suits, number, suits, Card},
-    deck::deck_ord,
-    game::{FieldKey, Game, Prompt, PromptKind},
+    card::Card,
+    combo::Combo,
+    effect::Resolution,
+    game::{FieldKey, Game, PromptKind},
+    interrupt::open_interrupt,
+    validator::{violations, Violation},
 };
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidateServe {
-    serves: Vec<Card>,
+    cards: Vec<Card>,
 }
 
 impl EventHandler for ValidateServe {
-    fn on(&self, _player_id: String, game: &mut Game) -> Result<Event> {
-        if !is_same_number(&self.serves) {
-            return Err(anyhow!("not same number"));
+    fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
+        let violations = violations(game, &player_id, &self.cards);
+        if !violations.is_empty() {
+            let reasons = violations.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+            return Err(anyhow!("{}", reasons.join(", ")));
         }
-        let Some(top) = game.river.last() else {
-            // river is empty
-            return Ok(Event::None);
-        };
-        // check ordering
-        let ordering = if game.revoluted ^ game.turn_revoluted {
-            deck_ord(&self.serves, top).reverse()
-        } else {
-            deck_ord(&self.serves, top)
-        };
-        if ordering.is_lt() {
-            return Err(anyhow!("must be greater than top card"));
+        Ok(Event::None)
+    }
+}
+
+/// validates serving the selected cards in hands of `player_id`
+fn validate_selection(player_id: &str, game: &mut Game) -> Result<Combo> {
+    game.check_blocked(player_id)?;
+    if game.current.as_deref() != Some(player_id) {
+        return Err(anyhow!("not your turn"));
+    }
+    let serves = game.selected(player_id, &FieldKey::Hands(player_id.to_string()));
+    if serves.is_empty() {
+        return Err(anyhow!("please select cards"));
+    }
+    let validate = ValidateServe {
+        cards: serves.clone(),
+    };
+    validate.on(player_id.to_string(), game)?;
+    Combo::new(player_id.to_string(), serves)
+}
+
+/// result of serving the current selection, computed without changing the game
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ServePreview {
+    pub legal: bool,
+    /// why the serve is not legal
+    pub reason: Option<String>,
+    /// every violated validator, empty if the selection is not checked
+    pub violations: Vec<Violation>,
+    /// players to advance after the serve, 0 means the server moves again
+    pub skips: Option<i32>,
+    pub revolution: bool,
+    /// prompt the serve opens
+    pub prompt: Option<PromptKind>,
+    pub effects: Vec<String>,
+    /// effects in the order they resolve
+    pub resolution: Vec<Resolution>,
+}
+
+/// runs `ValidateServe` and `EffectCard` on a copy of `game`
+pub fn preview_serve(player_id: &str, game: &Game) -> ServePreview {
+    let mut after = game.clone();
+    let combo = match validate_selection(player_id, &mut after) {
+        Ok(combo) => combo,
+        Err(e) => {
+            let serves = game.selected(player_id, &FieldKey::Hands(player_id.to_string()));
+            return ServePreview {
+                reason: Some(e.to_string()),
+                violations: violations(game, player_id, &serves),
+                ..Default::default()
+            };
         }
-        // check river size
-        let river_size = game.river_size.unwrap();
-        let expected_river_size = match number(&self.serves) {
-            9 if !game.effect_limits.contains(&9) => match river_size {
-                1 => 3,
-                3 => 1,
-                n => n,
-            },
-            _ => self.serves.len(),
+    };
+    let dry_run = |after: &mut Game| -> Result<()> {
+        after
+            .field_mut(&FieldKey::Hands(player_id.to_string()))?
+            .remove(&combo.cards)?;
+        after.river.push(combo.clone());
+        after.resolution.clear();
+        let event = EffectCard {
+            combo: combo.clone(),
+        };
+        event.on(player_id.to_string(), after)?;
+        Ok(())
+    };
+    if let Err(e) = dry_run(&mut after) {
+        return ServePreview {
+            reason: Some(e.to_string()),
+            ..Default::default()
         };
-        if river_size != expected_river_size {
-            return Err(anyhow!(
-                "expected river size {} but {}",
-                expected_river_size,
-                river_size
-            ));
-        }
-        // check steps
-        if game.is_step && cardinal(number(&self.serves)) - cardinal(number(top)) != 1 {
-            return Err(anyhow!("must be step"));
-        }
-        // check suits
-        if !game.suit_limits.is_empty() && !match_suits(top, &self.serves) {
-            return Err(anyhow!(
-                "expected suits {:?} but {:?}",
-                game.suit_limits,
-                suits(&self.serves)
-            ));
-        }
-        Ok(Event::None)
+    }
+
+    let revolution = after.revoluted != game.revoluted;
+    let prompt = after.prompt().map(|p| p.kind.clone());
+    let skips = after.skips(&combo);
+    let effects = after
+        .resolution
+        .iter()
+        .map(|r| r.description.clone())
+        .collect();
+    ServePreview {
+        legal: true,
+        reason: None,
+        violations: vec![],
+        skips: Some(skips),
+        revolution,
+        prompt,
+        effects,
+        resolution: after.resolution,
     }
 }
 
@@ -68,63 +119,66 @@ pub struct Serve;
 
 impl EventHandler for Serve {
     fn on(&self, player_id: String, game: &mut Game) -> Result<Event> {
-        if let Some(prompt) = game.prompt.first() {
-            if prompt.player_ids.contains(&player_id) && !game.answers.contains_key(&player_id) {
-                return Err(anyhow!("please answer"));
-            }
-        }
+        game.check_blocked(&player_id)?;
 
-        let serves = game.selects.get(&player_id).unwrap().clone();
+        let combo = validate_selection(&player_id, game);
         // reset select
-        game.selects.insert(player_id.to_string(), vec![]);
-
-        if game.current != Some(player_id.clone()) {
-            return Err(anyhow!("not your turn"));
-        }
-        if serves.is_empty() {
-            return Err(anyhow!("please select cards"));
-        }
-        let validate = ValidateServe {
-            serves: serves.clone(),
-        };
-        validate.on(player_id.clone(), game)?;
+        game.clear_selects(&player_id);
+        let combo = combo?;
 
         game.field_mut(&FieldKey::Hands(player_id.clone()))?
-            .remove(&serves)?;
-        game.river.push(serves.clone());
-
-        let has_1_player_ids = game
-            .active_player_ids()
-            .iter()
-            .filter(|id| {
-                id != &&player_id
-                    && game
-                        .field(&FieldKey::Hands(id.to_string()))
-                        .unwrap()
-                        .0
-                        .iter()
-                        .any(|c| c.number() == Some(1))
-            })
-            .cloned()
-            .collect::<Vec<_>>();
+            .remove(&combo.cards)?;
+        game.river.push(combo.clone());
+        // passes answered the previous top, not this one
+        game.passed_player_ids.clear();
+        game.resolution.clear();
 
-        if !game.effect_limits.contains(&1) && !has_1_player_ids.is_empty() {
-            let prompt = Prompt {
-                kind: PromptKind::UseOneChance,
-                player_ids: has_1_player_ids,
-                question: "select A if use one chance".to_string(),
-                options: vec!["serve".to_string(), "skip".to_string()],
-            };
-            game.prompt.push(prompt);
+        // the effect is resolved after the interrupt window
+        if !open_interrupt(game, &combo) {
+            let event = EffectCard { combo };
+            event.on(player_id.clone(), game)?;
         }
         // end phase
-        if game.prompt.is_empty() {
-            let player_id = game.current.clone().unwrap();
-            let event = EffectCard { serves };
-            event.on(player_id.clone(), game)?;
-            game.last_served_player_id = Some(player_id.to_string());
-            game.on_end_turn()?;
+        if game.prompts.is_empty() {
+            game.on_end_serve()?;
         }
         Ok(Event::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        events::{select::SelectMany, serve::Serve, EventHandler},
+        game::FieldKey,
+        testing::{cards, no_interrupts, select, serve, setup},
+    };
+
+    #[test]
+    fn joker_pair_leaves_the_hand() {
+        let mut game = setup(&["joker joker 4s", "5h 6h"], no_interrupts());
+        let hands = FieldKey::Hands("p0".to_string());
+        // the second click selects the other joker
+        select(&mut game, "p0", hands.clone(), "joker joker").unwrap();
+        assert_eq!(game.selected("p0", &hands), cards("joker joker"));
+        select(&mut game, "p0", hands.clone(), "joker").unwrap();
+        assert!(game.selected("p0", &hands).is_empty());
+
+        serve(&mut game, "p0", "joker joker").unwrap();
+        assert_eq!(game.fields[&hands].0, cards("4s"));
+        assert_eq!(game.river[0].cards, cards("joker joker"));
+    }
+
+    #[test]
+    fn joker_pair_selected_at_once_leaves_the_hand() {
+        let mut game = setup(&["joker joker 4s", "5h 6h"], no_interrupts());
+        let hands = FieldKey::Hands("p0".to_string());
+        let event = SelectMany {
+            field: hands.clone(),
+            cards: cards("joker joker"),
+        };
+        event.on("p0".to_string(), &mut game).unwrap();
+        Serve.on("p0".to_string(), &mut game).unwrap();
+        assert_eq!(game.fields[&hands].0, cards("4s"));
+    }
+}
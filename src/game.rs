@@ -1,30 +1,137 @@
 use crate::{
-    card::{number, Card, Suit},
+    card::{Card, Suit},
+    combo::Combo,
     deck::Deck,
+    effect::{EffectRegistry, Resolution},
+    interrupt::InterruptKind,
+    rules::{Rules, Shibari},
+    strength::Strength,
 };
 use anyhow::{anyhow, Result};
 use extism_pdk::{FromBytesOwned, ToBytes};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use std::collections::{HashMap, HashSet};
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
 pub enum PromptKind {
     Select4,
+    /// 7 passing to the previous player
     Select7,
+    /// 7 passing to the next player
+    Select7Next,
+    /// 10 discarding to trushes
+    Select10,
     Select13,
-    UseOneChance,
+    Interrupt(InterruptKind),
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+impl PromptKind {
+    /// field answers of `player_id` are selected from
+    pub fn field(&self, player_id: &str) -> FieldKey {
+        match self {
+            PromptKind::Select4 => FieldKey::Trushes,
+            PromptKind::Select13 => FieldKey::Excluded,
+            PromptKind::Select7
+            | PromptKind::Select7Next
+            | PromptKind::Select10
+            | PromptKind::Interrupt(_) => FieldKey::Hands(player_id.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct Prompt {
     pub kind: PromptKind,
+    /// players who must answer before the prompt resolves
     pub player_ids: Vec<String>,
     pub question: String,
     pub options: Vec<String>,
+    pub answers: HashMap<String, String>,
+    /// cards each player had selected when answering
+    pub selections: HashMap<String, Vec<Card>>,
+    /// bounds of the number of cards to select
+    pub min: usize,
+    pub max: usize,
+    /// cards each player may select
+    pub candidates: HashMap<String, Vec<Card>>,
+    /// field selected cards come from, `None` if hands of each player
+    pub source: Option<FieldKey>,
+    /// field selected cards go to, `None` if the river
+    pub target: Option<FieldKey>,
+}
+
+impl Prompt {
+    pub fn new(
+        kind: PromptKind,
+        player_ids: Vec<String>,
+        question: &str,
+        options: &[&str],
+    ) -> Self {
+        Self {
+            kind,
+            player_ids,
+            question: question.to_string(),
+            options: options.iter().map(|o| o.to_string()).collect(),
+            answers: HashMap::new(),
+            selections: HashMap::new(),
+            min: 0,
+            max: 0,
+            candidates: HashMap::new(),
+            source: None,
+            target: None,
+        }
+    }
+
+    pub fn with_count(self, min: usize, max: usize) -> Self {
+        Self { min, max, ..self }
+    }
+
+    pub fn with_candidates(self, candidates: HashMap<String, Vec<Card>>) -> Self {
+        Self { candidates, ..self }
+    }
+
+    pub fn with_fields(self, source: Option<FieldKey>, target: Option<FieldKey>) -> Self {
+        Self {
+            source,
+            target,
+            ..self
+        }
+    }
+
+    pub fn candidates(&self, player_id: &str) -> Vec<Card> {
+        self.candidates.get(player_id).cloned().unwrap_or_default()
+    }
+
+    /// whether each player has answered, in order of `player_ids`
+    pub fn statuses(&self) -> Vec<(String, bool)> {
+        self.player_ids
+            .iter()
+            .map(|id| (id.clone(), self.answers.contains_key(id)))
+            .collect()
+    }
+
+    /// players who have not answered yet
+    pub fn pending_player_ids(&self) -> Vec<String> {
+        self.player_ids
+            .iter()
+            .filter(|id| !self.answers.contains_key(*id))
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_answered(&self) -> bool {
+        self.pending_player_ids().is_empty()
+    }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+/// serialized as `trushes`, `excluded` or `hands:<player_id>`
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum FieldKey {
     Trushes,
     Excluded,
@@ -36,39 +143,117 @@ impl std::fmt::Display for FieldKey {
         match self {
             FieldKey::Trushes => write!(f, "trushes"),
             FieldKey::Excluded => write!(f, "excluded"),
-            FieldKey::Hands(id) => write!(f, "{}", id),
+            FieldKey::Hands(id) => write!(f, "hands:{}", id),
+        }
+    }
+}
+
+impl TryFrom<String> for FieldKey {
+    type Error = FieldError;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        match s.as_str() {
+            "trushes" => Ok(FieldKey::Trushes),
+            "excluded" => Ok(FieldKey::Excluded),
+            _ => match s.split_once(':') {
+                Some(("hands", id)) if !id.is_empty() => Ok(FieldKey::Hands(id.to_string())),
+                _ => Err(FieldError::Unknown(s)),
+            },
+        }
+    }
+}
+
+impl From<FieldKey> for String {
+    fn from(key: FieldKey) -> Self {
+        key.to_string()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FieldError {
+    /// not a field name
+    Unknown(String),
+    /// a field the player cannot select from, e.g. another player's hands
+    Foreign(FieldKey),
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldError::Unknown(s) => write!(f, "unknown field {}", s),
+            FieldError::Foreign(key) => write!(f, "cannot select from {}", key),
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+/// order turns go around the seats
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Direction {
+    /// in seating order
+    #[default]
+    Forward,
+    Backward,
+}
+
+impl Direction {
+    pub fn sign(&self) -> i32 {
+        match self {
+            Direction::Forward => 1,
+            Direction::Backward => -1,
+        }
+    }
+
+    pub fn reversed(&self) -> Self {
+        match self {
+            Direction::Forward => Direction::Backward,
+            Direction::Backward => Direction::Forward,
         }
     }
 }
 
 #[serde_as]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Game {
+    pub rules: Rules,
+    /// built from `rules` on first use, rebuilt after loading
+    #[serde(skip)]
+    effect_registry: OnceCell<Rc<EffectRegistry>>,
     // game state
-    pub prompt: Vec<Prompt>,
+    /// stack of open prompts, the last one is active and blocks the table
+    pub prompts: Vec<Prompt>,
     // workaround for "key must be a string" error
     // https://stackoverflow.com/questions/51276896/how-do-i-use-serde-to-serialize-a-hashmap-with-structs-as-keys-to-json
     #[serde_as(as = "Vec<(_, _)>")]
     pub fields: HashMap<FieldKey, Deck>,
     // river
-    pub river: Vec<Vec<Card>>,
+    pub river: Vec<Combo>,
     pub river_size: Option<usize>,
     pub suit_limits: HashSet<Suit>,
     /// a number includes `effect_limits` ignore effect
     pub effect_limits: HashSet<u8>,
     /// card strength is reversed until the river is reset
     pub turn_revoluted: bool,
-    /// when `is_step` is true, delta of previous cards number must be 1
+    /// when `is_step` is true, serves must be one step stronger than the top
     pub is_step: bool,
     /// when `revoluted` is true, card strength is reversed
     pub revoluted: bool,
+    /// effects of the last serve in the order they resolved
+    pub resolution: Vec<Resolution>,
+    /// indices of `rules.effects` whose trigger and conditions held when the top was served
+    pub matched_effects: Vec<usize>,
 
     pub current: Option<String>,
+    pub direction: Direction,
     pub last_served_player_id: Option<String>,
+    /// players who passed since the last serve in the current trick
+    pub passed_player_ids: HashSet<String>,
     // player state
     pub players: Vec<String>,
-    pub selects: HashMap<String, Vec<Card>>,
-    pub answers: HashMap<String, String>,
+    /// selected cards of each player per field
+    #[serde_as(as = "HashMap<_, Vec<(_, _)>>")]
+    pub selects: HashMap<String, HashMap<FieldKey, Vec<Card>>>,
 }
 
 impl ToBytes<'_> for Game {
@@ -86,7 +271,7 @@ impl FromBytesOwned for Game {
 }
 
 impl Game {
-    pub fn new(player_ids: Vec<String>) -> Self {
+    pub fn new(player_ids: Vec<String>, rules: Rules) -> Self {
         let mut fields = player_ids
             .iter()
             .map(|id| (FieldKey::Hands(id.clone()), Deck::new(vec![])))
@@ -95,7 +280,9 @@ impl Game {
         fields.insert(FieldKey::Excluded, Deck::new(vec![]));
 
         Self {
-            prompt: vec![],
+            rules,
+            effect_registry: OnceCell::new(),
+            prompts: vec![],
             fields,
 
             river: vec![],
@@ -105,13 +292,18 @@ impl Game {
             turn_revoluted: false,
             is_step: false,
             revoluted: false,
+            resolution: vec![],
+            matched_effects: vec![],
 
             current: None,
+            direction: Direction::Forward,
             last_served_player_id: None,
+            passed_player_ids: HashSet::new(),
 
             players: player_ids.clone(),
-            answers: HashMap::new(),
-            selects: HashMap::from_iter(player_ids.iter().map(|id| (id.to_string(), Vec::new()))),
+            selects: HashMap::from_iter(
+                player_ids.iter().map(|id| (id.to_string(), HashMap::new())),
+            ),
         }
     }
 
@@ -129,6 +321,61 @@ impl Game {
         Ok(deck)
     }
 
+    /// cards `player_id` selected in `field`
+    pub fn selected(&self, player_id: &str, field: &FieldKey) -> Vec<Card> {
+        self.selects
+            .get(player_id)
+            .and_then(|selects| selects.get(field))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// selects one more copy of `card` in `field`, or deselects every copy once all are selected.
+    /// equal cards such as jokers are selected one at a time
+    pub fn toggle_select(&mut self, player_id: &str, field: &FieldKey, card: Card) -> Result<()> {
+        let n_copies = self.field(field)?.0.iter().filter(|c| *c == &card).count();
+        if n_copies == 0 {
+            return Err(anyhow!("{} is not in {}", card, field));
+        }
+        let selects = self
+            .selects
+            .entry(player_id.to_string())
+            .or_default()
+            .entry(field.clone())
+            .or_default();
+        if selects.iter().filter(|c| *c == &card).count() < n_copies {
+            selects.push(card);
+        } else {
+            selects.retain(|c| c != &card);
+        }
+        Ok(())
+    }
+
+    /// replaces the selection of `player_id` in `field` with `cards`
+    pub fn select_many(
+        &mut self,
+        player_id: &str,
+        field: &FieldKey,
+        cards: Vec<Card>,
+    ) -> Result<()> {
+        let deck = self.field(field)?;
+        for card in cards.iter() {
+            let n_selected = cards.iter().filter(|c| c == &card).count();
+            if deck.0.iter().filter(|c| c == &card).count() < n_selected {
+                return Err(anyhow!("{} is not in {}", card, field));
+            }
+        }
+        self.selects
+            .entry(player_id.to_string())
+            .or_default()
+            .insert(field.clone(), cards);
+        Ok(())
+    }
+
+    pub fn clear_selects(&mut self, player_id: &str) {
+        self.selects.insert(player_id.to_string(), HashMap::new());
+    }
+
     pub fn transfer(&mut self, from: &FieldKey, to: &FieldKey, cards: Vec<Card>) -> Result<()> {
         self.field_mut(from)?.remove(&cards)?;
         self.field_mut(to)?.0.extend(cards.to_vec());
@@ -149,31 +396,263 @@ impl Game {
             .collect()
     }
 
+    /// every other player in turn order, starting from the one after `player_id` in `direction`
+    pub fn seats_after(&self, player_id: &str) -> Vec<String> {
+        let n_seats = self.players.len() as i32;
+        let index = self
+            .players
+            .iter()
+            .position(|id| id == player_id)
+            .unwrap_or_default() as i32;
+        (1..n_seats)
+            .map(|d| {
+                let index = (index + d * self.direction.sign()).rem_euclid(n_seats);
+                self.players[index as usize].clone()
+            })
+            .collect()
+    }
+
+    /// active player `d` seats away from `player_id` in seating order, counting active players only.
+    /// when `player_id` is finished, 0 means the next active player in `direction`
     pub fn get_relative_player(&self, player_id: &str, d: i32) -> String {
         let active_player_ids = self.active_player_ids();
-        let index = active_player_ids
+        let is_active = active_player_ids.iter().any(|id| id == player_id);
+        if active_player_ids.is_empty() || (d == 0 && is_active) {
+            return player_id.to_string();
+        }
+        let step = match d {
+            0 => self.direction.sign(),
+            d => d.signum(),
+        };
+        let n_seats = self.players.len() as i32;
+        let mut index = self
+            .players
             .iter()
             .position(|id| id == player_id)
-            .unwrap();
-        let index = ((index as i32 + d).rem_euclid(active_player_ids.len() as i32)) as usize;
-        active_player_ids[index].clone()
+            .unwrap_or_default() as i32;
+        let mut remaining = d.abs().max(1);
+        loop {
+            index = (index + step).rem_euclid(n_seats);
+            let id = &self.players[index as usize];
+            if active_player_ids.contains(id) {
+                remaining -= 1;
+                if remaining == 0 {
+                    return id.clone();
+                }
+            }
+        }
+    }
+
+    /// active player `n` turns after `player_id` in `direction`
+    pub fn next_player(&self, player_id: &str, n: i32) -> String {
+        self.get_relative_player(player_id, n * self.direction.sign())
+    }
+
+    /// active prompt
+    pub fn prompt(&self) -> Option<&Prompt> {
+        self.prompts.last()
+    }
+
+    pub fn prompt_mut(&mut self) -> Option<&mut Prompt> {
+        self.prompts.last_mut()
+    }
+
+    /// opens `prompt` on top of the stack, it becomes active until resolved or cancelled
+    pub fn open_prompt(&mut self, prompt: Prompt) {
+        self.prompts.push(prompt);
+    }
+
+    /// closes the active prompt after every player answered
+    pub fn resolve_prompt(&mut self) -> Result<Prompt> {
+        let Some(prompt) = self.prompt() else {
+            return Err(anyhow!("no prompt"));
+        };
+        if !prompt.is_answered() {
+            return Err(anyhow!(
+                "waiting for {} to answer",
+                prompt.pending_player_ids().join(", ")
+            ));
+        }
+        Ok(self.prompts.pop().unwrap())
+    }
+
+    /// closes the active prompt without applying answers
+    pub fn cancel_prompt(&mut self) -> Result<Prompt> {
+        self.prompts.pop().ok_or(anyhow!("no prompt"))
+    }
+
+    /// returns an error while a prompt blocks the table
+    pub fn check_blocked(&self, player_id: &str) -> Result<()> {
+        let Some(prompt) = self.prompt() else {
+            return Ok(());
+        };
+        let pending_player_ids = prompt.pending_player_ids();
+        if pending_player_ids.iter().any(|id| id == player_id) {
+            return Err(anyhow!("please answer: {}", prompt.question));
+        }
+        Err(anyhow!(
+            "waiting for {} to answer: {}",
+            pending_player_ids.join(", "),
+            prompt.question
+        ))
+    }
+
+    /// effects of each number under the rules of this game
+    pub fn effects(&self) -> Rc<EffectRegistry> {
+        self.effect_registry
+            .get_or_init(|| Rc::new(EffectRegistry::new(&self.rules)))
+            .clone()
+    }
+
+    /// number whose effect `combo` triggers, `None` if it has no effect.
+    /// jokers only combo triggers `rules.joker_effect`
+    pub fn effect_number(&self, combo: &Combo) -> Option<u8> {
+        let n = combo.rank.or(self.rules.joker_effect)?;
+        let effects = self.effects();
+        let effect = effects.get(n)?;
+        (!effect.is_suppressed(self, n)).then_some(n)
+    }
+
+    /// players to advance after `combo`, 0 means the server moves again
+    pub fn skips(&self, combo: &Combo) -> i32 {
+        let effects = self.effects();
+        match self.effect_number(combo).and_then(|n| effects.get(n)) {
+            Some(effect) => effect.on_end_turn(self, combo),
+            None => 1,
+        }
+    }
+
+    /// field the river is flushed to while `combo` is on top
+    pub fn flush_target(&self, combo: &Combo) -> FieldKey {
+        let effects = self.effects();
+        match self.effect_number(combo).and_then(|n| effects.get(n)) {
+            Some(effect) => effect.on_flush(self, combo),
+            None => FieldKey::Trushes,
+        }
+    }
+
+    /// card strength under the current revolution and 11-back
+    pub fn strength(&self) -> Strength {
+        Strength::new(
+            self.revoluted ^ self.turn_revoluted,
+            self.rules.rank_order.ranks(),
+        )
+    }
+
+    /// sorts every hand weakest first in the current strength
+    pub fn sort_hands(&mut self) {
+        let strength = self.strength();
+        for (key, deck) in self.fields.iter_mut() {
+            if let FieldKey::Hands(_) = key {
+                strength.sort(deck);
+            }
+        }
+    }
+
+    /// locks steps by `rules.kaidan` when `combo` is one step above the previous serve
+    pub fn update_step_lock(&mut self, combo: &Combo) {
+        if !self.rules.kaidan || self.is_step {
+            return;
+        }
+        let Some(index) = self.river.iter().rposition(|c| c == combo) else {
+            return;
+        };
+        let Some(prev) = index.checked_sub(1).map(|i| &self.river[i]) else {
+            return;
+        };
+        // the serve was checked to be stronger in the order it was served in,
+        // which this serve may have reversed since
+        if let (Some(n), Some(prev_n)) = (combo.rank, prev.rank) {
+            self.is_step = self.strength().is_adjacent(n, prev_n);
+        }
+    }
+
+    /// locks suits by `rules.shibari` when `combo` follows a serve of the same suits.
+    /// jokers stand for any suit, so they never break nor form a lock
+    pub fn update_suit_lock(&mut self, combo: &Combo) {
+        let Some(shibari) = &self.rules.shibari else {
+            return;
+        };
+        if !self.suit_limits.is_empty() {
+            return;
+        }
+        let Some(index) = self.river.iter().rposition(|c| c == combo) else {
+            return;
+        };
+        let Some(prev) = index.checked_sub(1).map(|i| &self.river[i]) else {
+            return;
+        };
+        // suits exclude jokers, a serve of only jokers locks nothing
+        if prev.suits.is_empty() || combo.suits.is_empty() {
+            return;
+        }
+        let suits = match shibari {
+            Shibari::Full => {
+                let suits = prev
+                    .suits
+                    .union(&combo.suits)
+                    .cloned()
+                    .collect::<HashSet<_>>();
+                // jokers of either serve must cover the suits the other one has
+                if prev.size != combo.size || suits.len() > combo.size {
+                    return;
+                }
+                suits
+            }
+            Shibari::Partial => prev.suits.intersection(&combo.suits).cloned().collect(),
+        };
+        self.suit_limits = suits;
     }
 
     fn flush_river(&mut self, to: &FieldKey) -> Result<()> {
-        let cards = self.river.iter().flatten().cloned().collect::<Vec<_>>();
+        let cards = self
+            .river
+            .iter()
+            .flat_map(|c| c.cards.clone())
+            .collect::<Vec<_>>();
         self.field_mut(to)?.0.extend(cards);
         self.river.clear();
 
         self.river_size = None;
         self.suit_limits = HashSet::new();
         self.effect_limits = HashSet::new();
+        // hands are sorted again when 11-back ends
+        let was_reversed = self.turn_revoluted;
         self.turn_revoluted = false;
         self.is_step = false;
+        self.matched_effects.clear();
+        self.passed_player_ids.clear();
+        if was_reversed {
+            self.sort_hands();
+        }
+
+        Ok(())
+    }
 
+    /// flushes the river at once, `leader` serves next,
+    /// or the next active player if `leader` is finished
+    pub fn cut(&mut self, leader: &str, to: &FieldKey) -> Result<()> {
+        self.flush_river(to)?;
+        let leader = self.next_player(leader, 0);
+        self.last_served_player_id = Some(leader.clone());
+        self.current = Some(leader);
         Ok(())
     }
 
-    pub fn on_end_turn(&mut self) -> Result<()> {
+    /// called when a serve and every prompt it opened are resolved
+    pub fn on_end_serve(&mut self) -> Result<()> {
+        let Some(top) = self.river.last() else {
+            // the river was cut and the leader is chosen
+            return Ok(());
+        };
+        let skips = self.skips(top);
+        self.last_served_player_id = Some(top.server.clone());
+        self.on_end_turn(skips)
+    }
+
+    /// advances `skips` players in the direction of play, effects of the top apply only
+    /// right after it is served, a pass moves to the next player
+    pub fn on_end_turn(&mut self, skips: i32) -> Result<()> {
         let player_id = self.current.clone().unwrap();
 
         let hand = self.field(&FieldKey::Hands(player_id.clone()))?;
@@ -185,25 +664,130 @@ impl Game {
             .river
             .last()
             .expect("river must not be empty when end turn");
+        let to = self.flush_target(top);
 
         // next player
-        let skips = match top {
-            _ if number(top) == 5 && !self.effect_limits.contains(&5) => top.len() as i32 + 1,
-            _ if number(top) == 8 && !self.effect_limits.contains(&8) => 0,
-            _ if number(top) == 1 && !self.effect_limits.contains(&1) => 0,
-            _ => 1,
-        };
-        self.current = Some(self.get_relative_player(&player_id, skips));
-
-        // flush
-        if self.current == self.last_served_player_id {
-            let to = if number(top) == 2 && !self.effect_limits.contains(&2) {
-                FieldKey::Excluded
-            } else {
-                FieldKey::Trushes
-            };
+        let next = self.next_player(&player_id, skips);
+        self.current = Some(next.clone());
+
+        // flush, when the last server is finished the first player to come back leads
+        let is_last_served = self.last_served_player_id.as_ref() == Some(&next);
+        let is_last_served_finished = self
+            .last_served_player_id
+            .as_ref()
+            .is_some_and(|id| !self.active_player_ids().contains(id));
+        if is_last_served || (is_last_served_finished && self.passed_player_ids.contains(&next)) {
             self.flush_river(&to)?;
+            self.last_served_player_id = Some(next);
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{cards, no_interrupts, pass, serve, setup};
+
+    fn river_lock(shibari: Shibari, prev: &str, top: &str) -> HashSet<Suit> {
+        let rules = Rules {
+            shibari: Some(shibari),
+            ..no_interrupts()
+        };
+        let mut game = setup(&["", ""], rules);
+        let top = Combo::new("p1".to_string(), cards(top)).unwrap();
+        game.river = vec![
+            Combo::new("p0".to_string(), cards(prev)).unwrap(),
+            top.clone(),
+        ];
+        game.update_suit_lock(&top);
+        game.suit_limits
+    }
+
+    #[test]
+    fn flushes_when_the_turn_returns_to_the_last_server() {
+        let mut game = setup(&["6s Ks", "4h 5h", "4d 5d"], no_interrupts());
+        serve(&mut game, "p0", "6s").unwrap();
+        pass(&mut game, "p1").unwrap();
+        assert_eq!(game.river.len(), 1);
+        pass(&mut game, "p2").unwrap();
+        assert!(game.river.is_empty());
+        assert_eq!(game.current.as_deref(), Some("p0"));
+        assert_eq!(game.fields[&FieldKey::Trushes].0, cards("6s"));
+    }
+
+    #[test]
+    fn first_player_to_come_back_leads_after_the_last_server_finishes() {
+        let mut game = setup(&["6s", "4h 5h", "4d 5d"], no_interrupts());
+        serve(&mut game, "p0", "6s").unwrap();
+        pass(&mut game, "p1").unwrap();
+        assert_eq!(game.river.len(), 1);
+        pass(&mut game, "p2").unwrap();
+        assert!(game.river.is_empty());
+        assert_eq!(game.current.as_deref(), Some("p1"));
+        assert_eq!(game.last_served_player_id.as_deref(), Some("p1"));
+    }
+
+    #[test]
+    fn passes_before_the_last_serve_do_not_flush() {
+        let mut game = setup(&["6s 2c", "4h 5h", "Td 3d"], no_interrupts());
+        serve(&mut game, "p0", "6s").unwrap();
+        pass(&mut game, "p1").unwrap();
+        serve(&mut game, "p2", "Td").unwrap();
+        // p0 finishes, p1 passed only against the 6
+        serve(&mut game, "p0", "2c").unwrap();
+        assert_eq!(game.river.len(), 3);
+        assert_eq!(game.current.as_deref(), Some("p1"));
+
+        pass(&mut game, "p1").unwrap();
+        assert_eq!(game.river.len(), 3);
+        pass(&mut game, "p2").unwrap();
+        assert!(game.river.is_empty());
+        assert_eq!(game.current.as_deref(), Some("p1"));
+    }
+
+    #[test]
+    fn skips_apply_only_right_after_the_serve() {
+        let mut game = setup(&["5s Ks", "4h 3h", "4d 3d", "4c 3c"], no_interrupts());
+        serve(&mut game, "p0", "5s").unwrap();
+        assert_eq!(game.current.as_deref(), Some("p2"));
+        pass(&mut game, "p2").unwrap();
+        assert_eq!(game.current.as_deref(), Some("p3"));
+        assert_eq!(game.river.len(), 1);
+        pass(&mut game, "p3").unwrap();
+        assert_eq!(game.current.as_deref(), Some("p0"));
+        assert!(game.river.is_empty());
+    }
+
+    #[test]
+    fn finished_cutter_hands_the_lead_to_the_next_active_player() {
+        let mut game = setup(&["8s", "4h 5h", "4d 5d"], no_interrupts());
+        game.direction = Direction::Backward;
+        serve(&mut game, "p0", "8s").unwrap();
+        assert!(game.river.is_empty());
+        assert_eq!(game.current.as_deref(), Some("p2"));
+    }
+
+    #[test]
+    fn full_shibari_locks_the_suits_of_both_serves() {
+        let lock = river_lock(Shibari::Full, "4s 4h", "5s 5h");
+        assert_eq!(lock, HashSet::from([Suit::Spade, Suit::Heart]));
+        // a joker stands in for the missing heart
+        let lock = river_lock(Shibari::Full, "4s joker", "5s 5h");
+        assert_eq!(lock, HashSet::from([Suit::Spade, Suit::Heart]));
+        assert!(river_lock(Shibari::Full, "4s 4h", "5s 5d").is_empty());
+        assert!(river_lock(Shibari::Full, "4s 4h", "5d joker").is_empty());
+        assert!(river_lock(Shibari::Full, "4s", "joker").is_empty());
+        assert!(river_lock(Shibari::Full, "joker", "5s").is_empty());
+    }
+
+    #[test]
+    fn partial_shibari_locks_the_shared_suits() {
+        let lock = river_lock(Shibari::Partial, "4s 4h", "5s 5d");
+        assert_eq!(lock, HashSet::from([Suit::Spade]));
+        let lock = river_lock(Shibari::Partial, "4s joker", "5s joker");
+        assert_eq!(lock, HashSet::from([Suit::Spade]));
+        assert!(river_lock(Shibari::Partial, "4s 4h", "5d 5c").is_empty());
+        assert!(river_lock(Shibari::Partial, "4s", "joker").is_empty());
+    }
+}
@@ -1,8 +1,11 @@
 use crate::{
-    card::Card,
-    game::{FieldKey, Game, Prompt},
+    card::{number_str, Card, Suit},
+    effect::Resolution,
+    events::serve::{preview_serve, ServePreview},
+    game::{Direction, FieldKey, Game, Prompt},
 };
 use anyhow::{anyhow, Result};
+use serde::Serialize;
 use tera::Tera;
 
 static APP_HTML: &[u8] = include_bytes!("templates/app.html");
@@ -10,28 +13,143 @@ static APP_HTML: &[u8] = include_bytes!("templates/app.html");
 /// text, data, selected
 type DeckView = Vec<(String, String, bool)>;
 
+/// cards served by a player in the current trick
+#[derive(Debug, Serialize)]
+pub struct RiverView {
+    player_id: String,
+    cards: DeckView,
+}
+
+/// per-seat summary in seating order
+#[derive(Debug, Serialize)]
+pub struct PlayerView {
+    id: String,
+    n_cards: usize,
+    is_me: bool,
+    is_current: bool,
+    is_last_served: bool,
+    is_passed: bool,
+    is_finished: bool,
+}
+
+/// rules currently in force for the river
+#[derive(Debug, Serialize)]
+pub struct TableView {
+    /// `revoluted ^ turn_revoluted`, card strength is reversed
+    is_reversed: bool,
+    weakest: String,
+    strongest: String,
+    revoluted: bool,
+    turn_revoluted: bool,
+    is_step: bool,
+    /// number required by the step lock, `None` if nothing can follow
+    step_number: Option<String>,
+    direction: Direction,
+    suit_limits: Vec<String>,
+    river_size: Option<usize>,
+    effect_limits: Vec<String>,
+}
+
 pub struct Ctx {
+    player_id: String,
     is_current: bool,
     current: Option<String>,
+    players: Vec<PlayerView>,
+    table: TableView,
     trushes: DeckView,
     excluded: DeckView,
-    river: DeckView,
+    river: Vec<RiverView>,
+    /// effects of the last serve in the order they resolved
+    resolution: Vec<Resolution>,
     hands: DeckView,
+    /// numbers in hands for bulk selection
+    hand_ranks: Vec<String>,
+    has_jokers: bool,
+    /// preview of serving the current selection, only for the current player
+    serve_preview: Option<ServePreview>,
+    /// viewer must answer the active prompt
     show_prompt: bool,
-    prompt: Vec<Prompt>,
+    /// active prompt
+    prompt: Option<Prompt>,
+    /// players the table is waiting for
+    waiting_for: Vec<String>,
+    /// whether each prompted player has answered
+    prompt_statuses: Vec<(String, bool)>,
+    /// cards the viewer may select to answer
+    prompt_candidates: DeckView,
+    /// field the viewer selects answers from
+    prompt_field: Option<String>,
 }
 
 impl Ctx {
     fn into_deck_view(cards: &[Card], selects: &[Card]) -> DeckView {
+        // a selected copy marks only one of equal cards
+        let mut selects = selects.to_vec();
         cards
             .iter()
-            .map(|c| (c.char().to_string(), c.to_string(), selects.contains(c)))
+            .map(|c| {
+                let index = selects.iter().position(|s| s == c);
+                if let Some(index) = index {
+                    selects.remove(index);
+                }
+                (c.char().to_string(), c.to_string(), index.is_some())
+            })
+            .collect()
+    }
+
+    fn into_player_views(game: &Game, player_id: &str) -> Result<Vec<PlayerView>> {
+        game.players
+            .iter()
+            .map(|id| {
+                let n_cards = game.field(&FieldKey::Hands(id.to_string()))?.0.len();
+                Ok(PlayerView {
+                    id: id.to_string(),
+                    n_cards,
+                    is_me: id == player_id,
+                    is_current: game.current.as_ref() == Some(id),
+                    is_last_served: game.last_served_player_id.as_ref() == Some(id),
+                    is_passed: game.passed_player_ids.contains(id),
+                    // hands are empty before distribution
+                    is_finished: game.current.is_some() && n_cards == 0,
+                })
+            })
             .collect()
     }
 
+    fn into_table_view(game: &Game) -> TableView {
+        let step_number = game
+            .river
+            .last()
+            .filter(|_| game.is_step)
+            .and_then(|top| top.rank)
+            .and_then(|n| game.strength().step_after(n))
+            .map(number_str);
+        let suit_limits = Suit::suits()
+            .into_iter()
+            .filter(|s| game.suit_limits.contains(s))
+            .map(|s| s.to_string())
+            .collect();
+        let mut effect_limits = game.effect_limits.iter().cloned().collect::<Vec<_>>();
+        let strength = game.strength();
+        effect_limits.sort_by(|l, r| strength.rank_cmp(*l, *r));
+        let (weakest, strongest) = strength.bounds();
+        TableView {
+            is_reversed: strength.reversed,
+            weakest: number_str(weakest),
+            strongest: number_str(strongest),
+            revoluted: game.revoluted,
+            turn_revoluted: game.turn_revoluted,
+            is_step: game.is_step,
+            step_number,
+            direction: game.direction,
+            suit_limits,
+            river_size: game.river_size,
+            effect_limits: effect_limits.into_iter().map(number_str).collect(),
+        }
+    }
+
     pub fn new(game: &Game, player_id: String) -> Result<Self> {
         let is_current = game.current == Some(player_id.clone());
-        let selects = &game.selects[&player_id];
 
         let trushes = Self::into_deck_view(
             &game
@@ -39,7 +157,7 @@ impl Ctx {
                 .get(&FieldKey::Trushes)
                 .ok_or(anyhow!("trushes not found"))?
                 .0,
-            &selects,
+            &game.selected(&player_id, &FieldKey::Trushes),
         );
         let excluded = Self::into_deck_view(
             &game
@@ -47,46 +165,98 @@ impl Ctx {
                 .get(&FieldKey::Excluded)
                 .ok_or(anyhow!("excluded not found"))?
                 .0,
-            &selects,
+            &game.selected(&player_id, &FieldKey::Excluded),
         );
-        let river = Self::into_deck_view(game.river.last().unwrap_or(&vec![]), &[]);
+        let river = game
+            .river
+            .iter()
+            .map(|combo| RiverView {
+                player_id: combo.server.clone(),
+                cards: Self::into_deck_view(&combo.cards, &[]),
+            })
+            .collect();
+        let hand_cards = &game
+            .fields
+            .get(&FieldKey::Hands(player_id.to_string()))
+            .ok_or(anyhow!("hands not found"))?
+            .0;
         let hands = Self::into_deck_view(
-            &game
-                .fields
-                .get(&FieldKey::Hands(player_id.to_string()))
-                .ok_or(anyhow!("hands not found"))?
-                .0,
-            &selects,
+            hand_cards,
+            &game.selected(&player_id, &FieldKey::Hands(player_id.clone())),
         );
+        let mut hand_ranks = vec![];
+        for n in hand_cards.iter().filter_map(|c| c.number()) {
+            if !hand_ranks.contains(&number_str(n)) {
+                hand_ranks.push(number_str(n));
+            }
+        }
+        let has_jokers = hand_cards.iter().any(|c| c.number().is_none());
 
-        let show_prompt = game
-            .prompt
-            .last()
-            .map(|p| p.player_ids.contains(&player_id) && !game.answers.contains_key(&player_id))
-            .unwrap_or(false);
+        let players = Self::into_player_views(game, &player_id)?;
+        let table = Self::into_table_view(game);
+
+        let serve_preview = is_current.then(|| preview_serve(&player_id, game));
+
+        let prompt = game.prompt().cloned();
+        let waiting_for = prompt
+            .as_ref()
+            .map(|p| p.pending_player_ids())
+            .unwrap_or_default();
+        let show_prompt = waiting_for.contains(&player_id);
+        let prompt_statuses = prompt.as_ref().map(|p| p.statuses()).unwrap_or_default();
+        let prompt_field = prompt.as_ref().map(|p| p.kind.field(&player_id));
+        let prompt_candidates = match (&prompt, &prompt_field) {
+            (Some(p), Some(field)) => {
+                Self::into_deck_view(&p.candidates(&player_id), &game.selected(&player_id, field))
+            }
+            _ => vec![],
+        };
+        let prompt_field = prompt_field.map(|f| f.to_string());
 
         Ok(Self {
+            player_id,
             is_current,
             current: game.current.clone(),
+            players,
+            table,
             trushes,
             excluded,
             river,
+            resolution: game.resolution.clone(),
             hands,
+            hand_ranks,
+            has_jokers,
+            serve_preview,
             show_prompt,
-            prompt: game.prompt.clone(),
+            prompt,
+            waiting_for,
+            prompt_statuses,
+            prompt_candidates,
+            prompt_field,
         })
     }
 
     pub fn render(&self) -> Result<String> {
         let mut context = tera::Context::new();
+        context.insert("player_id", &self.player_id);
         context.insert("is_current", &self.is_current);
         context.insert("current", &self.current);
+        context.insert("players", &self.players);
+        context.insert("table", &self.table);
         context.insert("trushes", &self.trushes);
         context.insert("excluded", &self.excluded);
         context.insert("river", &self.river);
+        context.insert("resolution", &self.resolution);
         context.insert("hands", &self.hands);
+        context.insert("hand_ranks", &self.hand_ranks);
+        context.insert("has_jokers", &self.has_jokers);
+        context.insert("serve_preview", &self.serve_preview);
         context.insert("show_prompt", &self.show_prompt);
         context.insert("prompt", &self.prompt);
+        context.insert("waiting_for", &self.waiting_for);
+        context.insert("prompt_statuses", &self.prompt_statuses);
+        context.insert("prompt_candidates", &self.prompt_candidates);
+        context.insert("prompt_field", &self.prompt_field);
 
         let html = Tera::one_off(std::str::from_utf8(APP_HTML)?, &context, false)?;
         Ok(html)
@@ -0,0 +1,146 @@
+use crate::{
+    card::{Card, Suit},
+    combo::Combo,
+    game::{FieldKey, Game, Prompt, PromptKind},
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// out of turn counter-play against a serve
+pub trait Interrupt {
+    fn question(&self, server: &str) -> String;
+    /// cards `player_id` may counter `top` with, empty if not eligible
+    fn candidates(&self, game: &Game, player_id: &str, top: &Combo) -> Vec<Card>;
+    /// when true, the counter flushes the river and its server leads
+    fn cuts(&self) -> bool;
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub enum InterruptKind {
+    /// any A can be served over anything
+    OneChance,
+    /// 3 of spades can be served over a single joker
+    SpadeThree,
+}
+
+impl InterruptKind {
+    pub fn interrupt(&self) -> Box<dyn Interrupt> {
+        match self {
+            InterruptKind::OneChance => Box::new(OneChance),
+            InterruptKind::SpadeThree => Box::new(SpadeThree),
+        }
+    }
+}
+
+pub struct OneChance;
+
+impl Interrupt for OneChance {
+    fn question(&self, server: &str) -> String {
+        format!(
+            "select A to use one chance, the nearest seat after {} wins",
+            server
+        )
+    }
+
+    fn candidates(&self, game: &Game, player_id: &str, _top: &Combo) -> Vec<Card> {
+        if game.effect_limits.contains(&1) {
+            return vec![];
+        }
+        let Ok(hands) = game.field(&FieldKey::Hands(player_id.to_string())) else {
+            return vec![];
+        };
+        hands
+            .0
+            .iter()
+            .filter(|c| c.number() == Some(1))
+            .cloned()
+            .collect()
+    }
+
+    fn cuts(&self) -> bool {
+        true
+    }
+}
+
+pub struct SpadeThree;
+
+impl Interrupt for SpadeThree {
+    fn question(&self, server: &str) -> String {
+        format!(
+            "select 3 of spades to counter the joker, the nearest seat after {} wins",
+            server
+        )
+    }
+
+    fn candidates(&self, game: &Game, player_id: &str, top: &Combo) -> Vec<Card> {
+        if top.rank.is_some() || top.size != 1 {
+            return vec![];
+        }
+        let spade_three = Card::Number(Suit::Spade, 3);
+        let Ok(hands) = game.field(&FieldKey::Hands(player_id.to_string())) else {
+            return vec![];
+        };
+        if !hands.0.contains(&spade_three) {
+            return vec![];
+        }
+        vec![spade_three]
+    }
+
+    fn cuts(&self) -> bool {
+        true
+    }
+}
+
+/// opens a window for the first interrupt in `rules.interrupts` some other active player
+/// is eligible for. returns false if no window is opened
+pub fn open_interrupt(game: &mut Game, top: &Combo) -> bool {
+    for kind in game.rules.interrupts.clone() {
+        let interrupt = kind.interrupt();
+        let candidates = game
+            .active_player_ids()
+            .into_iter()
+            .filter(|id| id != &top.server)
+            .map(|id| {
+                let cards = interrupt.candidates(game, &id, top);
+                (id, cards)
+            })
+            .filter(|(_, cards)| !cards.is_empty())
+            .collect::<HashMap<_, _>>();
+        if candidates.is_empty() {
+            continue;
+        }
+        let player_ids = game
+            .seats_after(&top.server)
+            .into_iter()
+            .filter(|id| candidates.contains_key(id))
+            .collect();
+        let prompt = Prompt::new(
+            PromptKind::Interrupt(kind),
+            player_ids,
+            &interrupt.question(&top.server),
+            &["serve", "skip"],
+        )
+        .with_count(0, 1)
+        .with_candidates(candidates);
+        game.open_prompt(prompt);
+        return true;
+    }
+    false
+}
+
+/// moves the counter `winner` answered `prompt` with to the river, it becomes the current player
+pub fn serve_counter(game: &mut Game, winner: &str, prompt: &Prompt) -> Result<Combo> {
+    let serves = prompt.selections.get(winner).cloned().unwrap_or_default();
+    let candidates = prompt.candidates(winner);
+    if serves.len() != 1 || !candidates.contains(&serves[0]) {
+        return Err(anyhow!("invalid counter of {}", winner));
+    }
+    let hands = FieldKey::Hands(winner.to_string());
+    game.field_mut(&hands)?.remove(&serves)?;
+    let counter = Combo::new(winner.to_string(), serves)?;
+    game.river.push(counter.clone());
+    game.passed_player_ids.clear();
+    game.current = Some(winner.to_string());
+    Ok(counter)
+}
@@ -1,28 +1,45 @@
 use crate::game_view::Ctx;
 use anyhow::anyhow;
-use card::Card;
+use card::{number_from_str, Card};
 use events::{
-    answer::Answer, distribute::Distribute, pass::Pass, select::Select, serve::Serve, Event,
-    EventHandler,
+    answer::Answer,
+    distribute::Distribute,
+    pass::Pass,
+    select::{ClearSelection, Select, SelectMany, SelectRank},
+    serve::{Serve, ServePreview},
+    Event, EventHandler,
 };
 use extism_pdk::*;
 use game::Game;
+use rules::Rules;
 
 pub mod card;
+pub mod combo;
 pub mod deck;
+pub mod effect;
+pub mod effect_def;
+pub mod interrupt;
+pub mod rules;
+pub mod strength;
+pub mod validator;
 
 mod events;
 mod game;
 mod game_view;
+#[cfg(test)]
+mod testing;
 
 #[derive(serde::Deserialize)]
 pub struct GameConfig {
     pub player_ids: Vec<String>,
+    #[serde(default)]
+    pub rules: Rules,
 }
 
 #[plugin_fn]
 pub fn init_game(Json(config): Json<GameConfig>) -> FnResult<()> {
-    let game = Game::new(config.player_ids);
+    config.rules.validate()?;
+    let game = Game::new(config.player_ids, config.rules);
     var::set("game", &game)?;
     Ok(())
 }
@@ -47,6 +64,18 @@ pub fn into_event_handler(event: &Event) -> anyhow::Result<Option<Box<dyn EventH
             field: field.clone(),
             card: Card::try_from(card.as_str())?,
         }))),
+        Event::SelectMany { field, cards } => Ok(Some(Box::new(SelectMany {
+            field: field.clone(),
+            cards: cards
+                .iter()
+                .map(|c| Card::try_from(c.as_str()))
+                .collect::<Result<_, _>>()?,
+        }))),
+        Event::SelectRank { rank, jokers } => Ok(Some(Box::new(SelectRank {
+            rank: number_from_str(rank)?,
+            jokers: *jokers,
+        }))),
+        Event::ClearSelection => Ok(Some(Box::new(ClearSelection))),
         Event::Answer { option } => Ok(Some(Box::new(Answer {
             answer: option.clone(),
         }))),
@@ -69,6 +98,20 @@ pub fn handle_event(
     Ok(res)
 }
 
+#[derive(serde::Deserialize)]
+pub struct PreviewServeArg {
+    pub player_id: String,
+}
+
+/// validates the selection of `player_id` and describes its effects without serving
+#[plugin_fn]
+pub fn preview_serve(
+    Json(PreviewServeArg { player_id }): Json<PreviewServeArg>,
+) -> FnResult<Json<ServePreview>> {
+    let game: Game = var::get("game")?.ok_or(anyhow!("Game not found"))?;
+    Ok(Json(events::serve::preview_serve(&player_id, &game)))
+}
+
 #[derive(serde::Deserialize)]
 pub struct RenderConfig {
     pub player_id: String,
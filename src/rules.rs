@@ -0,0 +1,105 @@
+use crate::{effect_def::EffectDef, interrupt::InterruptKind, validator::ValidatorKind};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// order of numbers from the weakest when not reversed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum RankOrder {
+    /// 3 is the weakest and 2 the strongest
+    TwoHigh,
+    /// 2 is the weakest and A the strongest
+    AceHigh,
+    /// every number once, from the weakest
+    Custom(Vec<u8>),
+}
+
+impl RankOrder {
+    pub fn ranks(&self) -> Vec<u8> {
+        match self {
+            RankOrder::TwoHigh => vec![3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 1, 2],
+            RankOrder::AceHigh => (2..=13).chain([1]).collect(),
+            RankOrder::Custom(ranks) => ranks.clone(),
+        }
+    }
+}
+
+/// player a 7 passes cards to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum PassTarget {
+    Previous,
+    Next,
+}
+
+/// suit lock when a serve follows one of the same suits
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum Shibari {
+    /// locks when both serves have the same suits
+    Full,
+    /// locks the suits both serves share
+    Partial,
+}
+
+/// local rules chosen when the game is created
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Rules {
+    /// number whose effect a jokers only combo triggers, `None` means no effect
+    pub joker_effect: Option<u8>,
+    /// 4, 7 and 13 prompts accept fewer cards than served
+    pub select_up_to: bool,
+    /// counter-plays checked in order after each serve
+    pub interrupts: Vec<InterruptKind>,
+    /// local effects written as data, applied in order after built-in ones
+    pub effects: Vec<EffectDef>,
+    /// automatic suit lock, `None` means suits only lock through effects
+    pub shibari: Option<Shibari>,
+    /// steps lock when a serve is one step above the previous one
+    pub kaidan: bool,
+    /// player a 7 passes cards to
+    pub seven_pass: PassTarget,
+    /// 10 discards up to as many cards as served instead of disabling effects
+    pub ten_discard: bool,
+    /// 8 cuts the river only when served alone
+    pub eight_cut_single_only: bool,
+    /// strength of numbers before revolution
+    pub rank_order: RankOrder,
+    /// constraints every serve is checked against
+    pub validators: Vec<ValidatorKind>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            joker_effect: None,
+            select_up_to: false,
+            interrupts: vec![InterruptKind::OneChance],
+            effects: vec![],
+            shibari: None,
+            kaidan: false,
+            seven_pass: PassTarget::Previous,
+            ten_discard: false,
+            eight_cut_single_only: false,
+            rank_order: RankOrder::TwoHigh,
+            validators: ValidatorKind::all(),
+        }
+    }
+}
+
+impl Rules {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(n) = self.joker_effect {
+            if !(1..=13).contains(&n) {
+                return Err(anyhow!("invalid joker effect {}", n));
+            }
+        }
+        let mut ranks = self.rank_order.ranks();
+        ranks.sort();
+        if ranks != (1..=13).collect::<Vec<_>>() {
+            return Err(anyhow!("rank order must have every number once"));
+        }
+        for def in self.effects.iter() {
+            def.validate()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{card::Card, combo::Combo, deck::Deck};
+use std::cmp::Ordering;
+
+/// order of card strength in the current state of a game, every comparison goes through it.
+/// jokers are the strongest in any order
+#[derive(Debug, Clone)]
+pub struct Strength {
+    /// `revoluted ^ turn_revoluted`
+    pub reversed: bool,
+    /// numbers from the weakest when not reversed, see `RankOrder`
+    pub order: Vec<u8>,
+}
+
+impl Strength {
+    pub fn new(reversed: bool, order: Vec<u8>) -> Self {
+        Self { reversed, order }
+    }
+
+    /// position of `n` from the weakest when not reversed
+    pub fn cardinal(&self, n: u8) -> i32 {
+        self.order
+            .iter()
+            .position(|r| *r == n)
+            .expect("rank order must have every number") as i32
+    }
+
+    /// inverse of `cardinal`, returns `None` if out of range
+    pub fn from_cardinal(&self, c: i32) -> Option<u8> {
+        usize::try_from(c)
+            .ok()
+            .and_then(|c| self.order.get(c))
+            .cloned()
+    }
+
+    /// weakest and strongest numbers in the current order
+    pub fn bounds(&self) -> (u8, u8) {
+        let (first, last) = (self.order[0], self.order[self.order.len() - 1]);
+        if self.reversed {
+            (last, first)
+        } else {
+            (first, last)
+        }
+    }
+
+    pub fn rank_cmp(&self, l: u8, r: u8) -> Ordering {
+        let ordering = self.cardinal(l).cmp(&self.cardinal(r));
+        if self.reversed {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    pub fn card_cmp(&self, l: &Card, r: &Card) -> Ordering {
+        match (l.number(), r.number()) {
+            (None, None) => Ordering::Equal,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(l), Some(r)) => self.rank_cmp(l, r),
+        }
+    }
+
+    /// a combo of only jokers beats any number
+    pub fn combo_cmp(&self, l: &Combo, r: &Combo) -> Ordering {
+        match (l.rank, r.rank) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(l), Some(r)) => self.rank_cmp(l, r),
+        }
+    }
+
+    /// number one step stronger than `n`, `None` if nothing can follow
+    pub fn step_after(&self, n: u8) -> Option<u8> {
+        let d = if self.reversed { -1 } else { 1 };
+        self.from_cardinal(self.cardinal(n) + d)
+    }
+
+    /// `l` and `r` are next to each other in either direction
+    pub fn is_adjacent(&self, l: u8, r: u8) -> bool {
+        (self.cardinal(l) - self.cardinal(r)).abs() == 1
+    }
+
+    /// sorts weakest first
+    pub fn sort(&self, deck: &mut Deck) {
+        deck.sort(|l, r| self.card_cmp(l, r));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rules::RankOrder, testing::cards};
+
+    fn combo(s: &str) -> Combo {
+        Combo::new("p0".to_string(), cards(s)).unwrap()
+    }
+
+    #[test]
+    fn revolution_reverses_numbers_but_not_jokers() {
+        let (three, two, joker) = (combo("3s"), combo("2s"), combo("joker"));
+
+        let strength = Strength::new(false, RankOrder::TwoHigh.ranks());
+        assert!(strength.combo_cmp(&three, &two).is_lt());
+        assert!(strength.combo_cmp(&joker, &two).is_gt());
+        assert!(strength.combo_cmp(&combo("5s"), &combo("5h")).is_eq());
+
+        let strength = Strength::new(true, RankOrder::TwoHigh.ranks());
+        assert!(strength.combo_cmp(&three, &two).is_gt());
+        assert!(strength.combo_cmp(&joker, &three).is_gt());
+        assert_eq!(strength.bounds(), (2, 3));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    card::Card,
+    deck::Deck,
+    events::{answer::Answer, pass::Pass, select::Select, serve::Serve, EventHandler},
+    game::{FieldKey, Game},
+    rules::Rules,
+};
+use anyhow::Result;
+
+/// cards from space separated notation, e.g. "3h joker"
+pub fn cards(s: &str) -> Vec<Card> {
+    s.split_whitespace()
+        .map(|c| Card::try_from(c).unwrap())
+        .collect()
+}
+
+/// players "p0", "p1", ... holding `hands`, "p0" moves first
+pub fn setup(hands: &[&str], rules: Rules) -> Game {
+    let player_ids = (0..hands.len())
+        .map(|i| format!("p{}", i))
+        .collect::<Vec<_>>();
+    let mut game = Game::new(player_ids.clone(), rules);
+    for (id, hand) in player_ids.iter().zip(hands) {
+        game.fields
+            .insert(FieldKey::Hands(id.clone()), Deck::new(cards(hand)));
+    }
+    game.current = Some(player_ids[0].clone());
+    game
+}
+
+/// rules without interrupt windows, so serves resolve at once
+pub fn no_interrupts() -> Rules {
+    Rules {
+        interrupts: vec![],
+        ..Rules::default()
+    }
+}
+
+pub fn select(game: &mut Game, player_id: &str, field: FieldKey, s: &str) -> Result<()> {
+    for card in cards(s) {
+        let field = field.clone();
+        Select { field, card }.on(player_id.to_string(), game)?;
+    }
+    Ok(())
+}
+
+pub fn serve(game: &mut Game, player_id: &str, s: &str) -> Result<()> {
+    select(game, player_id, FieldKey::Hands(player_id.to_string()), s)?;
+    Serve.on(player_id.to_string(), game)?;
+    Ok(())
+}
+
+pub fn pass(game: &mut Game, player_id: &str) -> Result<()> {
+    Pass.on(player_id.to_string(), game)?;
+    Ok(())
+}
+
+/// answers the active prompt with the cards in `s` selected from the hand
+pub fn answer(game: &mut Game, player_id: &str, option: &str, s: &str) -> Result<()> {
+    select(game, player_id, FieldKey::Hands(player_id.to_string()), s)?;
+    let answer = option.to_string();
+    Answer { answer }.on(player_id.to_string(), game)?;
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use crate::{
+    card::{number_str, numbers, Card, Suit},
+    combo::Combo,
+    game::Game,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt::Display};
+
+/// one constraint a serve must satisfy
+pub trait ServeValidator {
+    /// reason the cards cannot be served, `combo` is `None` if they do not form one
+    fn check(&self, game: &Game, cards: &[Card], combo: Option<&Combo>) -> Option<Violation>;
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub enum ValidatorKind {
+    /// all non-joker cards share a number
+    SameRank,
+    /// stronger than the top of the river
+    Stronger,
+    /// as many cards as the river size
+    SizeMatches,
+    /// one step above the top while the step lock is on
+    Step,
+    /// includes the locked suits
+    SuitLock,
+}
+
+impl ValidatorKind {
+    pub fn validator(&self) -> Box<dyn ServeValidator> {
+        match self {
+            ValidatorKind::SameRank => Box::new(SameRank),
+            ValidatorKind::Stronger => Box::new(Stronger),
+            ValidatorKind::SizeMatches => Box::new(SizeMatches),
+            ValidatorKind::Step => Box::new(Step),
+            ValidatorKind::SuitLock => Box::new(SuitLock),
+        }
+    }
+
+    pub fn all() -> Vec<ValidatorKind> {
+        vec![
+            ValidatorKind::SameRank,
+            ValidatorKind::Stronger,
+            ValidatorKind::SizeMatches,
+            ValidatorKind::Step,
+            ValidatorKind::SuitLock,
+        ]
+    }
+}
+
+/// why a serve is illegal
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(tag = "name", content = "value")]
+pub enum Violation {
+    MixedRanks,
+    Weaker,
+    SizeMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// `expected` is `None` if nothing can follow the top
+    NotStep {
+        expected: Option<u8>,
+    },
+    SuitMismatch {
+        expected: Vec<Suit>,
+        actual: Vec<Suit>,
+    },
+}
+
+fn sorted_suits(suits: &HashSet<Suit>) -> Vec<Suit> {
+    Suit::suits()
+        .into_iter()
+        .filter(|s| suits.contains(s))
+        .collect()
+}
+
+fn suits_str(suits: &[Suit]) -> String {
+    suits
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::MixedRanks => write!(f, "not same number"),
+            Violation::Weaker => write!(f, "must be greater than top card"),
+            Violation::SizeMismatch { expected, actual } => {
+                write!(f, "expected {} cards but {}", expected, actual)
+            }
+            Violation::NotStep { expected: Some(n) } => {
+                write!(f, "must be step to {}", number_str(*n))
+            }
+            Violation::NotStep { expected: None } => write!(f, "must be step"),
+            Violation::SuitMismatch { expected, actual } => write!(
+                f,
+                "expected suits {} but {}",
+                suits_str(expected),
+                suits_str(actual)
+            ),
+        }
+    }
+}
+
+pub struct SameRank;
+
+impl ServeValidator for SameRank {
+    fn check(&self, _game: &Game, cards: &[Card], _combo: Option<&Combo>) -> Option<Violation> {
+        (numbers(cards).len() > 1).then_some(Violation::MixedRanks)
+    }
+}
+
+pub struct Stronger;
+
+impl ServeValidator for Stronger {
+    fn check(&self, game: &Game, _cards: &[Card], combo: Option<&Combo>) -> Option<Violation> {
+        let (combo, top) = (combo?, game.river.last()?);
+        game.strength()
+            .combo_cmp(combo, top)
+            .is_lt()
+            .then_some(Violation::Weaker)
+    }
+}
+
+pub struct SizeMatches;
+
+impl ServeValidator for SizeMatches {
+    fn check(&self, game: &Game, _cards: &[Card], combo: Option<&Combo>) -> Option<Violation> {
+        let combo = combo?;
+        // checked before the combo resolves, a 9 flips the size only afterwards
+        let river_size = game.river_size?;
+        (combo.size != river_size).then_some(Violation::SizeMismatch {
+            expected: river_size,
+            actual: combo.size,
+        })
+    }
+}
+
+pub struct Step;
+
+impl ServeValidator for Step {
+    fn check(&self, game: &Game, _cards: &[Card], combo: Option<&Combo>) -> Option<Violation> {
+        if !game.is_step {
+            return None;
+        }
+        let (n, top_n) = (combo?.rank?, game.river.last()?.rank?);
+        let expected = game.strength().step_after(top_n);
+        (expected != Some(n)).then_some(Violation::NotStep { expected })
+    }
+}
+
+pub struct SuitLock;
+
+impl ServeValidator for SuitLock {
+    fn check(&self, game: &Game, _cards: &[Card], combo: Option<&Combo>) -> Option<Violation> {
+        let combo = combo?;
+        if game.suit_limits.is_empty() || combo.matches_suits(&game.suit_limits) {
+            return None;
+        }
+        Some(Violation::SuitMismatch {
+            expected: sorted_suits(&game.suit_limits),
+            actual: sorted_suits(&combo.suits),
+        })
+    }
+}
+
+/// every violation of `rules.validators` serving `cards` from `server`
+pub fn violations(game: &Game, server: &str, cards: &[Card]) -> Vec<Violation> {
+    let combo = Combo::new(server.to_string(), cards.to_vec()).ok();
+    game.rules
+        .validators
+        .iter()
+        .filter_map(|kind| kind.validator().check(game, cards, combo.as_ref()))
+        .collect()
+}